@@ -6,6 +6,8 @@
 
 struct WeatherMaterial {
     time: f32,
+    // 风力累计产生的雪花偏移
+    wind_offset: vec2<f32>,
 };
 
 @group(2) @binding(0)
@@ -27,6 +29,7 @@ fn fragment(
 		let fi: f32 = f32(i);
 		var q: vec2<f32> = in.uv * (5.5 + fi * DEPTH);
 		q += vec2(SPEED*weather.time/(1.+fi*DEPTH*.3),q.x*(WIDTH*(fi*7.238917%1.)-WIDTH*.5));
+		q -= weather.wind_offset * (5.5 + fi * DEPTH) / (1.+fi*DEPTH*.3);
 		let n: vec3<f32> = vec3<f32>(floor(q), 31.189 + fi);
 		let m: vec3<f32> = floor(n) * 0.00001 + fract(n);
 		let mp: vec3<f32> = (31415.9 + m) / fract(p * m);
@@ -0,0 +1,224 @@
+// 关卡检查工具，不打开窗口直接读取.ldtk文件，每个问题输出一行JSON
+// 用法：cargo run --bin celeste-lint -- [assets/levels.ldtk]
+use bevy::math::Vec2;
+use bevy_ecs_ldtk::ldtk::{LdtkJson, Level, Type};
+use bevy_ecs_ldtk::prelude::*;
+use celeste::ldtk_rules;
+use serde_json::json;
+use std::path::Path;
+use std::process::ExitCode;
+
+const DEFAULT_LDTK_PATH: &str = "assets/levels.ldtk";
+
+// 可以与其他实体重叠的区域实体
+const AREA_ENTITIES: &[&str] = &["Trigger", "WindZone"];
+
+// 检查出的问题
+struct Problem {
+    kind: &'static str,
+    level: String,
+    entity: Option<(String, String)>,
+    message: String,
+}
+
+impl Problem {
+    fn new(kind: &'static str, level: &Level, message: String) -> Problem {
+        Problem {
+            kind,
+            level: level.identifier.clone(),
+            entity: None,
+            message,
+        }
+    }
+
+    fn with_entity(mut self, entity_instance: &EntityInstance) -> Problem {
+        self.entity = Some((
+            entity_instance.identifier.clone(),
+            entity_instance.iid.clone(),
+        ));
+        self
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "kind": self.kind,
+            "level": self.level,
+            "entity": self.entity.as_ref().map(|(identifier, _)| identifier),
+            "iid": self.entity.as_ref().map(|(_, iid)| iid),
+            "message": self.message,
+        })
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+// 读取所有关卡，外部关卡文件相对.ldtk文件所在目录
+fn load_levels(path: &Path) -> Result<Vec<Level>, String> {
+    let project: LdtkJson = serde_json::from_str(&read_file(path)?)
+        .map_err(|error| format!("{}: {}", path.display(), error))?;
+    let levels = project
+        .levels
+        .into_iter()
+        .chain(project.worlds.into_iter().flat_map(|world| world.levels));
+    if !project.external_levels {
+        return Ok(levels.collect());
+    }
+    let base = path.parent().unwrap_or(Path::new(""));
+    levels
+        .map(|level| match &level.external_rel_path {
+            Some(rel_path) => {
+                let level_path = base.join(rel_path);
+                serde_json::from_str(&read_file(&level_path)?)
+                    .map_err(|error| format!("{}: {}", level_path.display(), error))
+            }
+            None => Ok(level),
+        })
+        .collect()
+}
+
+// 实体在关卡像素坐标中的矩形，(左上, 右下)
+fn entity_rect(entity_instance: &EntityInstance) -> (Vec2, Vec2) {
+    let size = Vec2::new(entity_instance.width as f32, entity_instance.height as f32);
+    let min = entity_instance.px.as_vec2() - entity_instance.pivot * size;
+    (min, min + size)
+}
+
+fn lint_level(level: &Level, problems: &mut Vec<Problem>) {
+    let layers = level.layer_instances.as_deref().unwrap_or_default();
+    let entities: Vec<&EntityInstance> = layers
+        .iter()
+        .filter(|layer| matches!(layer.layer_instance_type, Type::Entities))
+        .flat_map(|layer| layer.entity_instances.iter())
+        .collect();
+
+    let players = entities
+        .iter()
+        .filter(|entity_instance| entity_instance.identifier == "Player")
+        .count();
+    match players {
+        0 => problems.push(Problem::new(
+            "player_missing",
+            level,
+            "level has no Player entity".to_string(),
+        )),
+        1 => {}
+        count => problems.push(Problem::new(
+            "player_multiple",
+            level,
+            format!("level has {} Player entities", count),
+        )),
+    }
+
+    for entity_instance in &entities {
+        if !ldtk_rules::ENTITY_IDENTIFIERS.contains(&entity_instance.identifier.as_str()) {
+            problems.push(
+                Problem::new(
+                    "unknown_entity",
+                    level,
+                    format!("entity {} is not registered", entity_instance.identifier),
+                )
+                .with_entity(entity_instance),
+            );
+        }
+        for field in ldtk_rules::missing_required_fields(entity_instance) {
+            problems.push(
+                Problem::new(
+                    "missing_field",
+                    level,
+                    format!("missing required field \"{}\"", field),
+                )
+                .with_entity(entity_instance),
+            );
+        }
+        let (min, max) = entity_rect(entity_instance);
+        if min.x < 0.0 || min.y < 0.0 || max.x > level.px_wid as f32 || max.y > level.px_hei as f32
+        {
+            problems.push(
+                Problem::new(
+                    "out_of_bounds",
+                    level,
+                    format!(
+                        "entity at {:?} extends outside the level ({}x{})",
+                        entity_instance.px, level.px_wid, level.px_hei
+                    ),
+                )
+                .with_entity(entity_instance),
+            );
+        }
+    }
+
+    // 区域实体以外的实体两两检查重叠
+    let solid_entities: Vec<&EntityInstance> = entities
+        .iter()
+        .copied()
+        .filter(|entity_instance| !AREA_ENTITIES.contains(&entity_instance.identifier.as_str()))
+        .collect();
+    for (i, a) in solid_entities.iter().enumerate() {
+        let (a_min, a_max) = entity_rect(a);
+        for b in &solid_entities[i + 1..] {
+            let (b_min, b_max) = entity_rect(b);
+            if a_min.x < b_max.x && b_min.x < a_max.x && a_min.y < b_max.y && b_min.y < a_max.y {
+                problems.push(
+                    Problem::new(
+                        "overlap",
+                        level,
+                        format!("overlaps {}({})", b.identifier, b.iid),
+                    )
+                    .with_entity(a),
+                );
+            }
+        }
+    }
+
+    // 其他IntGrid图层的值不作为地形使用
+    for layer in layers.iter().filter(|layer| {
+        matches!(layer.layer_instance_type, Type::IntGrid)
+            && layer.identifier == ldtk_rules::TERRAIN_LAYER
+    }) {
+        for (i, value) in layer.int_grid_csv.iter().enumerate() {
+            if *value == 0 || ldtk_rules::terrain_properties(*value).is_some() {
+                continue;
+            }
+            let width = layer.c_wid.max(1) as usize;
+            problems.push(Problem::new(
+                "unknown_int_grid_value",
+                level,
+                format!(
+                    "layer {} has unsupported value {} at ({}, {})",
+                    layer.identifier,
+                    value,
+                    i % width,
+                    i / width
+                ),
+            ));
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_LDTK_PATH.to_string());
+    let levels = match load_levels(Path::new(&path)) {
+        Ok(levels) => levels,
+        Err(error) => {
+            println!("{}", json!({ "kind": "load_error", "message": error }));
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut problems = Vec::new();
+    for level in &levels {
+        lint_level(level, &mut problems);
+    }
+    for problem in &problems {
+        println!("{}", problem.to_json());
+    }
+    if problems.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,304 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::Level;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    collectible::Collectible,
+    common::TILE_SIZE,
+    level::{HotReloadState, LevelHotReload, Player, StaleAfterReload},
+    player::KillPlayerEvent,
+};
+
+// 掉出关卡底部多远后死亡
+pub const LEVEL_KILL_MARGIN: f32 = TILE_SIZE * 2.0;
+// 关卡边缘空气墙的厚度
+const LEVEL_WALL_THICKNESS: f32 = TILE_SIZE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelEdge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl LevelEdge {
+    // LDtk相邻关卡的方向
+    fn from_dir(dir: &str) -> Option<LevelEdge> {
+        match dir {
+            "w" => Some(LevelEdge::Left),
+            "e" => Some(LevelEdge::Right),
+            "n" => Some(LevelEdge::Top),
+            "s" => Some(LevelEdge::Bottom),
+            _ => None,
+        }
+    }
+}
+
+// 关卡边界，挂在关卡实体上
+#[derive(Debug, Component, Clone, Default)]
+pub struct LevelBounds {
+    pub size: Vec2,
+    // LDtk世界坐标，y轴向下
+    pub world_pos: IVec2,
+    pub neighbours: Vec<(LevelEdge, String)>,
+}
+
+// 关卡边缘的空气墙
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct LevelWall;
+
+// 进入相邻关卡的位置，死亡后在这里复活，为None时使用关卡中的Player实体
+#[derive(Debug, Default, Resource)]
+pub struct RespawnPoint(pub Option<Vec2>);
+
+// range中没有被covered覆盖的区间
+fn uncovered_spans((start, end): (f32, f32), mut covered: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    covered.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut spans = Vec::new();
+    let mut cursor = start;
+    for (covered_start, covered_end) in covered {
+        if covered_start > cursor {
+            spans.push((cursor, covered_start.min(end)));
+        }
+        cursor = cursor.max(covered_end);
+    }
+    if cursor < end {
+        spans.push((cursor, end));
+    }
+    spans.retain(|(span_start, span_end)| span_end > span_start);
+    spans
+}
+
+// 关卡生成后记录边界，左、右、上边缘没有被相邻关卡覆盖的部分生成空气墙
+pub fn setup_level_bounds(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    q_level: Query<(Entity, &LevelIid)>,
+    q_project: Query<&LdtkProjectHandle>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
+) {
+    let Some(ldtk_project) = q_project
+        .get_single()
+        .ok()
+        .and_then(|handle| ldtk_projects.get(handle))
+    else {
+        return;
+    };
+    for event in level_events.read() {
+        let LevelEvent::Transformed(level_iid) = event else {
+            continue;
+        };
+        let Some(level) = ldtk_project
+            .iter_raw_levels()
+            .find(|level| level.iid == *level_iid.get())
+        else {
+            continue;
+        };
+        let Some((level_entity, _)) = q_level.iter().find(|(_, iid)| *iid == level_iid) else {
+            continue;
+        };
+        let bounds = LevelBounds {
+            size: Vec2::new(level.px_wid as f32, level.px_hei as f32),
+            world_pos: IVec2::new(level.world_x, level.world_y),
+            neighbours: level
+                .neighbours
+                .iter()
+                .filter_map(|neighbour| {
+                    LevelEdge::from_dir(&neighbour.dir)
+                        .map(|edge| (edge, neighbour.level_iid.clone()))
+                })
+                .collect(),
+        };
+
+        let size = bounds.size;
+        let world_pos = bounds.world_pos.as_vec2();
+        // 边上被相邻关卡覆盖的区间，左右边为本地y坐标，上下边为本地x坐标
+        let covered = |edge: LevelEdge| -> Vec<(f32, f32)> {
+            bounds
+                .neighbours
+                .iter()
+                .filter(|(neighbour_edge, _)| *neighbour_edge == edge)
+                .filter_map(|(_, iid)| ldtk_project.get_raw_level_by_iid(iid))
+                .map(|neighbour| match edge {
+                    LevelEdge::Left | LevelEdge::Right => (
+                        size.y - (neighbour.world_y + neighbour.px_hei) as f32 + world_pos.y,
+                        size.y - neighbour.world_y as f32 + world_pos.y,
+                    ),
+                    LevelEdge::Top | LevelEdge::Bottom => (
+                        neighbour.world_x as f32 - world_pos.x,
+                        (neighbour.world_x + neighbour.px_wid) as f32 - world_pos.x,
+                    ),
+                })
+                .collect()
+        };
+        let half = LEVEL_WALL_THICKNESS / 2.0;
+        // 墙沿边延伸出一个厚度，封住角落
+        let walls = [
+            (
+                LevelEdge::Left,
+                (-LEVEL_WALL_THICKNESS, size.y + LEVEL_WALL_THICKNESS),
+            ),
+            (
+                LevelEdge::Right,
+                (-LEVEL_WALL_THICKNESS, size.y + LEVEL_WALL_THICKNESS),
+            ),
+            (
+                LevelEdge::Top,
+                (-LEVEL_WALL_THICKNESS, size.x + LEVEL_WALL_THICKNESS),
+            ),
+        ];
+        commands.entity(level_entity).with_children(|parent| {
+            for (edge, range) in walls {
+                for (start, end) in uncovered_spans(range, covered(edge)) {
+                    let (middle, half_length) = ((start + end) / 2.0, (end - start) / 2.0);
+                    let (pos, half_size) = match edge {
+                        LevelEdge::Left => (Vec2::new(-half, middle), Vec2::new(half, half_length)),
+                        LevelEdge::Right => (
+                            Vec2::new(size.x + half, middle),
+                            Vec2::new(half, half_length),
+                        ),
+                        LevelEdge::Top | LevelEdge::Bottom => (
+                            Vec2::new(middle, size.y + half),
+                            Vec2::new(half_length, half),
+                        ),
+                    };
+                    parent.spawn((
+                        LevelWall,
+                        Collider::cuboid(half_size.x, half_size.y),
+                        RigidBody::Fixed,
+                        Restitution::new(0.0),
+                        Transform::from_translation(pos.extend(0.0)),
+                    ));
+                }
+            }
+        });
+        commands.entity(level_entity).insert(bounds);
+    }
+}
+
+// 进入相邻关卡后等待新关卡生成，期间不再检测边界，角色保持速度
+#[derive(Debug, Default, Resource)]
+pub struct LevelTransition(pub bool);
+
+// 新关卡生成或角色死亡后结束切换
+pub fn level_transition_settle(
+    mut level_events: EventReader<LevelEvent>,
+    q_player: Query<(), With<Player>>,
+    mut transition: ResMut<LevelTransition>,
+) {
+    let spawned = level_events
+        .read()
+        .any(|event| matches!(event, LevelEvent::Spawned(_)));
+    if transition.0 && (spawned || q_player.is_empty()) {
+        transition.0 = false;
+    }
+}
+
+// 从LDtk项目中查找相邻关卡
+#[derive(SystemParam)]
+pub struct NeighbourLevels<'w, 's> {
+    q_project: Query<'w, 's, &'static LdtkProjectHandle>,
+    ldtk_projects: Res<'w, Assets<LdtkProject>>,
+}
+
+impl NeighbourLevels<'_, '_> {
+    // 包含LDtk世界坐标world的相邻关卡
+    fn containing(&self, bounds: &LevelBounds, world: Vec2) -> Option<&Level> {
+        let ldtk_project = self
+            .q_project
+            .get_single()
+            .ok()
+            .and_then(|handle| self.ldtk_projects.get(handle))?;
+        bounds
+            .neighbours
+            .iter()
+            .filter_map(|(_, iid)| ldtk_project.get_raw_level_by_iid(iid))
+            .find(|level| {
+                world.x >= level.world_x as f32
+                    && world.x <= (level.world_x + level.px_wid) as f32
+                    && world.y >= level.world_y as f32
+                    && world.y <= (level.world_y + level.px_hei) as f32
+            })
+    }
+}
+
+// 切换关卡时修改的资源
+#[derive(SystemParam)]
+pub struct LevelSwitch<'w> {
+    level_selection: ResMut<'w, LevelSelection>,
+    respawn_point: ResMut<'w, RespawnPoint>,
+    transition: ResMut<'w, LevelTransition>,
+    hot_reload: Res<'w, LevelHotReload>,
+}
+
+impl LevelSwitch<'_> {
+    // 切换关卡或热重载过程中旧关卡还未移除
+    fn pending(&self) -> bool {
+        self.transition.0 || self.hot_reload.0 != HotReloadState::Idle
+    }
+
+    fn enter(&mut self, level_iid: &str, pos: Vec2) {
+        self.respawn_point.0 = Some(pos);
+        *self.level_selection = LevelSelection::iid(level_iid);
+        self.transition.0 = true;
+    }
+}
+
+// 角色离开关卡：掉出底部死亡，越过有相邻关卡的边缘时切换到相邻关卡
+pub fn level_bounds_check(
+    mut commands: Commands,
+    mut q_player: Query<&mut Transform, With<Player>>,
+    q_level: Query<(&LevelBounds, &GlobalTransform)>,
+    q_stale: Query<(Entity, Option<&Collectible>), StaleAfterReload>,
+    neighbour_levels: NeighbourLevels,
+    mut level_switch: LevelSwitch,
+    mut kill_player_ew: EventWriter<KillPlayerEvent>,
+) {
+    if level_switch.pending() {
+        return;
+    }
+    let Ok(mut player_transform) = q_player.get_single_mut() else {
+        return;
+    };
+    let Ok((bounds, level_transform)) = q_level.get_single() else {
+        return;
+    };
+    let level_pos = level_transform.translation().truncate();
+    let local = player_transform.translation.truncate() - level_pos;
+    if local.x >= 0.0 && local.x <= bounds.size.x && local.y >= 0.0 && local.y <= bounds.size.y {
+        return;
+    }
+
+    // 角色在LDtk世界坐标中的位置
+    let world = Vec2::new(
+        bounds.world_pos.x as f32 + local.x,
+        bounds.world_pos.y as f32 + bounds.size.y - local.y,
+    );
+    let Some(neighbour) = neighbour_levels.containing(bounds, world) else {
+        // 没有相邻关卡接住时，掉出底部死亡
+        if local.y < -LEVEL_KILL_MARGIN {
+            kill_player_ew.send_default();
+        }
+        return;
+    };
+
+    info!("Enter level {}", neighbour.identifier);
+    // 所有关卡都生成在同一原点，按LDtk世界坐标换算到相邻关卡的本地坐标
+    let neighbour_local = Vec2::new(
+        world.x - neighbour.world_x as f32,
+        neighbour.px_hei as f32 - (world.y - neighbour.world_y as f32),
+    );
+    let pos = level_pos + neighbour_local;
+    player_transform.translation.x = pos.x;
+    player_transform.translation.y = pos.y;
+    level_switch.enter(&neighbour.iid, pos);
+    // 跟随角色的收集品带到新关卡
+    for (entity, collectible) in &q_stale {
+        if !matches!(collectible, Some(Collectible::Following(_))) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    common::TILE_SIZE,
+    level::Player,
+    platform::PlatformPath,
+    player::{DashOverEvent, PlayerCannotMoveTime, PlayerDashAvailable},
+    state_machine::PlayerState,
+};
+
+// 弹开角色的速度
+const BUMPER_BOUNCE_SPEED: f32 = 220.0;
+// 两次弹开之间的冷却时间
+const BUMPER_COOLDOWN: f32 = 0.4;
+// 挤压动画时长
+const BUMPER_SQUASH_TIME: f32 = 0.2;
+// 挤压幅度
+const BUMPER_SQUASH_SCALE: f32 = 0.3;
+// 弹开后不能控制左右移动的时间
+const BUMPER_CANNOT_MOVE_TIME: f32 = 0.2;
+const BUMPER_COLOR: Color = Color::srgb(1.0, 0.5, 0.2);
+const BUMPER_COOLDOWN_COLOR: Color = Color::srgb(0.5, 0.3, 0.2);
+
+// 弹球碰撞器，可沿Path移动
+#[derive(Debug, Component, Clone, Default)]
+pub struct Bumper {
+    pub size: Vec2,
+    pub path: PlatformPath,
+    // 剩余冷却时间
+    pub cooldown: f32,
+    // 剩余挤压动画时间
+    pub squash: f32,
+}
+
+impl From<&EntityInstance> for Bumper {
+    fn from(entity_instance: &EntityInstance) -> Bumper {
+        Bumper {
+            size: Vec2::new(entity_instance.width as f32, entity_instance.height as f32),
+            path: PlatformPath::from(entity_instance),
+            cooldown: 0.0,
+            squash: 0.0,
+        }
+    }
+}
+
+// 弹开角色
+pub fn bumper_bounce(
+    rapier_context: Single<&RapierContext>,
+    mut q_player: Query<(Entity, &Transform, &mut Velocity), With<Player>>,
+    mut q_bumper: Query<(Entity, &mut Bumper, &GlobalTransform)>,
+    mut dash_over_ew: EventWriter<DashOverEvent>,
+    player_state: Res<PlayerState>,
+    mut player_dash_available: ResMut<PlayerDashAvailable>,
+    mut player_cannot_move_time: ResMut<PlayerCannotMoveTime>,
+) {
+    let Ok((player_entity, player_transform, mut velocity)) = q_player.get_single_mut() else {
+        return;
+    };
+    for (entity, mut bumper, transform) in &mut q_bumper {
+        if bumper.cooldown > 0.0 {
+            continue;
+        }
+        if rapier_context.intersection_pair(player_entity, entity) != Some(true) {
+            continue;
+        }
+        info!("Bumper bounce");
+        let direction = (player_transform.translation.truncate()
+            - transform.translation().truncate())
+        .try_normalize()
+        .unwrap_or(Vec2::Y);
+        if *player_state == PlayerState::Dashing {
+            dash_over_ew.send(DashOverEvent {
+                keep_velocity: true,
+            });
+        }
+        velocity.linvel = direction * BUMPER_BOUNCE_SPEED;
+        player_cannot_move_time.0 = BUMPER_CANNOT_MOVE_TIME;
+        player_dash_available.0 = true;
+        bumper.cooldown = BUMPER_COOLDOWN;
+        bumper.squash = BUMPER_SQUASH_TIME;
+    }
+}
+
+// 移动、冷却与挤压动画
+pub fn bumper_update(
+    mut q_bumper: Query<(&mut Bumper, &mut Transform, &mut Sprite)>,
+    time: Res<Time>,
+) {
+    for (mut bumper, mut transform, mut sprite) in &mut q_bumper {
+        let path = &mut bumper.path;
+        let (speed, easing) = (path.speed, path.easing);
+        let movement = path.advance(speed, easing, time.delta_secs());
+        transform.translation += movement.extend(0.0);
+
+        bumper.cooldown = (bumper.cooldown - time.delta_secs()).max(0.0);
+        bumper.squash = (bumper.squash - time.delta_secs()).max(0.0);
+        // 被撞时横向拉伸、纵向压扁，逐渐恢复
+        let t = bumper.squash / BUMPER_SQUASH_TIME * BUMPER_SQUASH_SCALE;
+        let size = if bumper.size == Vec2::ZERO {
+            Vec2::splat(TILE_SIZE)
+        } else {
+            bumper.size
+        };
+        sprite.custom_size = Some(size * Vec2::new(1.0 + t, 1.0 - t));
+        sprite.color = if bumper.cooldown > 0.0 {
+            BUMPER_COOLDOWN_COLOR
+        } else {
+            BUMPER_COLOR
+        };
+    }
+}
@@ -18,6 +18,10 @@ pub enum CameraState {
     Shaking,
 }
 
+// 相机锁定位置，为None时跟随角色
+#[derive(Debug, Default, Resource)]
+pub struct CameraLock(pub Option<Vec2>);
+
 pub fn setup_camera(mut commands: Commands) {
     let mut projection = OrthographicProjection::default_2d();
     projection.scale = CAMERA_SCALE;
@@ -29,14 +33,18 @@ pub fn camera_follow(
     mut q_camera: Query<&mut Transform, (With<Camera>, Without<Player>)>,
     q_player: Query<&Transform, With<Player>>,
     camera_state: Res<CameraState>,
+    camera_lock: Res<CameraLock>,
 ) {
-    if q_player.is_empty() {
+    if q_player.is_empty() && camera_lock.0.is_none() {
         return;
     }
     if *camera_state != CameraState::Following {
         return;
     }
-    let player_pos = q_player.single().translation.truncate();
+    // 锁定时移向锁定位置
+    let player_pos = camera_lock
+        .0
+        .unwrap_or_else(|| q_player.single().translation.truncate());
     let camera_pos = q_camera.single().translation.truncate();
     let mut camera_transform = q_camera.single_mut();
     if camera_pos.distance(player_pos) < 0.1 {
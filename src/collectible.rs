@@ -0,0 +1,344 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    common::SPRITE_COLLECTIBLE_ORDER,
+    level::{Player, Strawberry},
+    player::{DashStartEvent, PlayerGrounded},
+};
+
+// 跟随链中相邻收集品间隔的帧数
+const COLLECTIBLE_FOLLOW_GAP: usize = 8;
+// 每帧逼近目标位置的百分比
+const COLLECTIBLE_FOLLOW_INTERPOLATE: f32 = 0.3;
+// 带翅膀草莓飞走速度
+const WINGED_STRAWBERRY_ESCAPE_SPEED: f32 = 120.0;
+// 带翅膀草莓飞出此距离后隐藏
+const WINGED_STRAWBERRY_ESCAPE_DISTANCE: f32 = 200.0;
+// 已收集草莓的存档文件
+const STRAWBERRY_SAVE_PATH: &str = "save/strawberries.json";
+
+// 收集品状态
+#[derive(Debug, Component, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Collectible {
+    #[default]
+    Idle,
+    // 跟随玩家，值越小越靠近玩家
+    Following(usize),
+    // 已飞走，玩家死亡后恢复
+    Escaped,
+}
+
+// 带翅膀的草莓，玩家冲刺时向上飞走
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct WingedStrawberry {
+    // 已向上飞行的距离
+    pub escape_offset: f32,
+}
+
+// 收集品原始位置，玩家死亡后放回
+#[derive(Debug, Component, Clone)]
+pub struct CollectibleOrigin {
+    pub parent: Entity,
+    pub translation: Vec3,
+    pub level_iid: String,
+}
+
+// 草莓收集事件
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct StrawberryCollectedEvent {
+    pub level_iid: String,
+    pub entity_iid: String,
+}
+
+// 每关已收集的草莓，key为关卡iid，value为草莓iid
+#[derive(Debug, Default, Resource)]
+pub struct StrawberryRecord(pub HashMap<String, HashSet<String>>);
+
+impl StrawberryRecord {
+    // 读取存档，没有存档时为空
+    pub fn load() -> StrawberryRecord {
+        let Ok(content) = std::fs::read_to_string(STRAWBERRY_SAVE_PATH) else {
+            return StrawberryRecord::default();
+        };
+        match serde_json::from_str(&content) {
+            Ok(record) => StrawberryRecord(record),
+            Err(error) => {
+                error!("{}: {}", STRAWBERRY_SAVE_PATH, error);
+                StrawberryRecord::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = std::path::Path::new(STRAWBERRY_SAVE_PATH);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&self.0)?)
+    }
+
+    pub fn count(&self, level_iid: &str) -> usize {
+        self.0
+            .get(level_iid)
+            .map_or(0, |strawberries| strawberries.len())
+    }
+
+    pub fn contains(&self, level_iid: &str, entity_iid: &str) -> bool {
+        self.0
+            .get(level_iid)
+            .is_some_and(|strawberries| strawberries.contains(entity_iid))
+    }
+}
+
+// 玩家触碰收集品，开始跟随
+pub fn collectible_touch(
+    mut commands: Commands,
+    mut collision_er: EventReader<CollisionEvent>,
+    mut q_collectible: Query<(Entity, &mut Collectible, &Transform, &Parent)>,
+    q_player: Query<(), With<Player>>,
+    q_parent: Query<&Parent>,
+    q_level: Query<&LevelIid>,
+) {
+    for event in collision_er.read() {
+        if let CollisionEvent::Started(entity1, entity2, _flags) = event {
+            let collectible_entity = if q_player.contains(*entity2) {
+                *entity1
+            } else if q_player.contains(*entity1) {
+                *entity2
+            } else {
+                continue;
+            };
+            let following_count = q_collectible
+                .iter()
+                .filter(|(_, collectible, _, _)| matches!(**collectible, Collectible::Following(_)))
+                .count();
+            let Ok((entity, mut collectible, transform, parent)) =
+                q_collectible.get_mut(collectible_entity)
+            else {
+                continue;
+            };
+            if *collectible != Collectible::Idle {
+                continue;
+            }
+            info!("Collectible touched");
+            let level_iid = q_parent
+                .iter_ancestors(entity)
+                .find_map(|ancestor| q_level.get(ancestor).ok())
+                .map(|level_iid| level_iid.get().clone())
+                .unwrap_or_default();
+            *collectible = Collectible::Following(following_count);
+            commands
+                .entity(entity)
+                .insert(CollectibleOrigin {
+                    parent: parent.get(),
+                    translation: transform.translation,
+                    level_iid,
+                })
+                .remove_parent_in_place();
+        }
+    }
+}
+
+// 收集品跟随玩家
+pub fn collectible_follow(
+    mut commands: Commands,
+    mut q_collectible: Query<(
+        Entity,
+        &mut Collectible,
+        &mut Transform,
+        Option<&CollectibleOrigin>,
+    )>,
+    q_player: Query<&Transform, (With<Player>, Without<Collectible>)>,
+    q_entity: Query<()>,
+    mut trail: Local<VecDeque<Vec2>>,
+) {
+    if q_player.is_empty() {
+        // 玩家死亡，收集品放回原处
+        trail.clear();
+        for (entity, mut collectible, mut transform, origin) in &mut q_collectible {
+            if !matches!(*collectible, Collectible::Following(_)) {
+                continue;
+            }
+            *collectible = Collectible::Idle;
+            if let Some(origin) = origin {
+                // 从其他关卡带来的收集品，原关卡已移除，再次进入时重新生成
+                if !q_entity.contains(origin.parent) {
+                    commands.entity(entity).despawn_recursive();
+                    continue;
+                }
+                transform.translation = origin.translation;
+                commands
+                    .entity(entity)
+                    .set_parent(origin.parent)
+                    .remove::<CollectibleOrigin>();
+            }
+        }
+        return;
+    }
+
+    let mut followers: Vec<(usize, Mut<Transform>)> = q_collectible
+        .iter_mut()
+        .filter_map(|(_, collectible, transform, _)| match *collectible {
+            Collectible::Following(order) => Some((order, transform)),
+            _ => None,
+        })
+        .collect();
+    followers.sort_by_key(|(order, _)| *order);
+
+    // trail记录最近若干帧player位置，收集品依次排在轨迹上
+    trail.push_front(q_player.single().translation.truncate());
+    trail.truncate((followers.len() + 1) * COLLECTIBLE_FOLLOW_GAP);
+
+    for (i, (_, transform)) in followers.iter_mut().enumerate() {
+        let target = trail[((i + 1) * COLLECTIBLE_FOLLOW_GAP).min(trail.len() - 1)];
+        let pos = transform.translation.truncate();
+        transform.translation = (pos + (target - pos) * COLLECTIBLE_FOLLOW_INTERPOLATE)
+            .extend(SPRITE_COLLECTIBLE_ORDER);
+    }
+}
+
+// 玩家落地后收下跟随的草莓
+pub fn strawberry_bank(
+    mut commands: Commands,
+    q_strawberry: Query<(Entity, &Collectible, &CollectibleOrigin, &EntityIid), With<Strawberry>>,
+    q_player: Query<(), With<Player>>,
+    player_grounded: Res<PlayerGrounded>,
+    mut strawberry_record: ResMut<StrawberryRecord>,
+    mut strawberry_collected_ew: EventWriter<StrawberryCollectedEvent>,
+) {
+    if q_player.is_empty() || !player_grounded.0 {
+        return;
+    }
+    for (entity, collectible, origin, entity_iid) in &q_strawberry {
+        if !matches!(collectible, Collectible::Following(_)) {
+            continue;
+        }
+        info!("Strawberry collected");
+        strawberry_record
+            .0
+            .entry(origin.level_iid.clone())
+            .or_default()
+            .insert(entity_iid.as_str().to_string());
+        strawberry_collected_ew.send(StrawberryCollectedEvent {
+            level_iid: origin.level_iid.clone(),
+            entity_iid: entity_iid.as_str().to_string(),
+        });
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// 收下草莓后写入存档
+pub fn save_strawberry_record(
+    mut strawberry_collected_er: EventReader<StrawberryCollectedEvent>,
+    strawberry_record: Res<StrawberryRecord>,
+) {
+    if strawberry_collected_er.read().count() == 0 {
+        return;
+    }
+    if let Err(error) = strawberry_record.save() {
+        error!("{}: {}", STRAWBERRY_SAVE_PATH, error);
+    }
+}
+
+// 关卡重新生成时，移除已收集过的草莓
+pub fn despawn_collected_strawberry(
+    mut commands: Commands,
+    q_strawberry: Query<(Entity, &EntityIid), Added<Strawberry>>,
+    q_parent: Query<&Parent>,
+    q_level: Query<&LevelIid>,
+    strawberry_record: Res<StrawberryRecord>,
+) {
+    for (entity, entity_iid) in &q_strawberry {
+        let Some(level_iid) = q_parent
+            .iter_ancestors(entity)
+            .find_map(|ancestor| q_level.get(ancestor).ok())
+        else {
+            continue;
+        };
+        if strawberry_record.contains(level_iid.get(), entity_iid.as_str()) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// 判断实体是否在当前选中的关卡中
+#[derive(SystemParam)]
+pub struct CurrentLevel<'w, 's> {
+    q_parent: Query<'w, 's, &'static Parent>,
+    q_level: Query<'w, 's, &'static LevelIid>,
+    q_project: Query<'w, 's, &'static LdtkProjectHandle>,
+    ldtk_projects: Res<'w, Assets<LdtkProject>>,
+    level_selection: Res<'w, LevelSelection>,
+}
+
+impl CurrentLevel<'_, '_> {
+    pub fn level_iid(&self) -> Option<String> {
+        self.q_project
+            .get_single()
+            .ok()
+            .and_then(|handle| self.ldtk_projects.get(handle))
+            .and_then(|ldtk_project| {
+                ldtk_project.find_raw_level_by_level_selection(&self.level_selection)
+            })
+            .map(|level| level.iid.clone())
+    }
+
+    pub fn contains(&self, entity: Entity, current_level_iid: Option<&String>) -> bool {
+        self.q_parent
+            .iter_ancestors(entity)
+            .find_map(|ancestor| self.q_level.get(ancestor).ok())
+            .is_some_and(|level_iid| Some(level_iid.get()) == current_level_iid)
+    }
+}
+
+// 玩家冲刺时带翅膀的草莓飞走，玩家死亡后飞回原处
+pub fn winged_strawberry_escape(
+    mut q_winged: Query<(
+        Entity,
+        &mut WingedStrawberry,
+        &mut Collectible,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    q_player: Query<(), With<Player>>,
+    current_level: CurrentLevel,
+    mut dash_start_er: EventReader<DashStartEvent>,
+    time: Res<Time>,
+) {
+    if dash_start_er.read().next().is_some() {
+        // 只有当前关卡中的草莓会飞走
+        let current_level_iid = current_level.level_iid();
+        for (entity, _, mut collectible, _, _) in &mut q_winged {
+            if *collectible == Collectible::Idle
+                && current_level.contains(entity, current_level_iid.as_ref())
+            {
+                info!("Winged strawberry escaped");
+                *collectible = Collectible::Escaped;
+            }
+        }
+    }
+
+    for (_, mut winged, mut collectible, mut transform, mut visibility) in &mut q_winged {
+        if *collectible != Collectible::Escaped {
+            continue;
+        }
+        if q_player.is_empty() {
+            transform.translation.y -= winged.escape_offset;
+            winged.escape_offset = 0.0;
+            *visibility = Visibility::Inherited;
+            *collectible = Collectible::Idle;
+            continue;
+        }
+        if winged.escape_offset < WINGED_STRAWBERRY_ESCAPE_DISTANCE {
+            let distance = WINGED_STRAWBERRY_ESCAPE_SPEED * time.delta_secs();
+            transform.translation.y += distance;
+            winged.escape_offset += distance;
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
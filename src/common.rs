@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
 
 pub const CAMERA_SCALE: f32 = 0.25;
 pub const TILE_SIZE: f32 = 8.0;
@@ -17,7 +18,11 @@ pub const PLAYER_RUN_SPEED: f32 = 50.0;
 // 滑落速度（攀爬）
 pub const PLAYER_SLIDE_SPEED: f32 = 20.0;
 
+// 碰撞分组
+pub const DREAM_BLOCK_GROUP: Group = Group::GROUP_2;
+
 // sprite z轴顺序
+pub const SPRITE_COLLECTIBLE_ORDER: f32 = 1.5;
 pub const SPRITE_DUST_ORDER: f32 = 2.0;
 pub const SPRITE_HAIR_ORDER: f32 = 3.0;
 pub const SPRITE_PLAYER_ORDER: f32 = 4.0;
@@ -27,6 +32,10 @@ pub enum AppState {
     #[default]
     StartMenu,
     Gaming,
+    // 触碰旗帜后的庆祝动画
+    LevelComplete,
+    // 结算界面
+    Results,
 }
 
 #[derive(Component, Clone, Default, Debug)]
@@ -0,0 +1,156 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    common::{DREAM_BLOCK_GROUP, PLAYER_DASH_SPEED, PLAYER_GRAVITY_SCALE, TILE_SIZE},
+    level::Player,
+    player::{Facing, KillPlayerEvent, PlayerDashAvailable},
+    state_machine::PlayerState,
+};
+
+// 梦境方块颜色
+const DREAM_BLOCK_COLOR: Color = Color::srgb(0.1, 0.05, 0.2);
+// 穿越梦境方块时角色颜色
+const PLAYER_DREAM_DASHING_COLOR: Color = Color::srgba(0.8, 0.6, 1.0, 0.6);
+
+// 梦境方块，冲刺时可穿过
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct DreamBlock;
+
+// 开始穿越梦境方块事件
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Event)]
+pub struct DreamDashStartEvent;
+// 穿越梦境方块结束事件
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Event)]
+pub struct DreamDashOverEvent;
+
+pub fn setup_dream_block(
+    mut commands: Commands,
+    mut q_dream_block: Query<(Entity, &mut Sprite), Added<DreamBlock>>,
+) {
+    for (entity, mut sprite) in &mut q_dream_block {
+        sprite.color = DREAM_BLOCK_COLOR;
+        commands
+            .entity(entity)
+            .insert(CollisionGroups::new(DREAM_BLOCK_GROUP, Group::ALL));
+    }
+}
+
+// 冲刺期间角色不与梦境方块碰撞
+pub fn dream_block_pass_through(
+    mut q_player: Query<&mut CollisionGroups, With<Player>>,
+    player_state: Res<PlayerState>,
+) {
+    let collision_groups = match *player_state {
+        PlayerState::Dashing | PlayerState::DreamDashing => {
+            CollisionGroups::new(Group::ALL, !DREAM_BLOCK_GROUP)
+        }
+        _ => CollisionGroups::default(),
+    };
+    for mut player_collision_groups in &mut q_player {
+        if *player_collision_groups != collision_groups {
+            *player_collision_groups = collision_groups;
+        }
+    }
+}
+
+// 穿越梦境方块时需要修改的角色组件
+type DreamDashPlayer = (
+    Entity,
+    &'static Transform,
+    &'static Facing,
+    &'static mut Velocity,
+    &'static mut GravityScale,
+    &'static mut Sprite,
+);
+
+// 穿越梦境方块时发送的事件
+#[derive(SystemParam)]
+pub struct DreamDashEvents<'w> {
+    dream_dash_start_ew: EventWriter<'w, DreamDashStartEvent>,
+    dream_dash_over_ew: EventWriter<'w, DreamDashOverEvent>,
+    kill_player_ew: EventWriter<'w, KillPlayerEvent>,
+}
+
+// 穿越梦境方块
+pub fn player_dream_dash(
+    rapier_context: Single<&RapierContext>,
+    mut q_player: Query<DreamDashPlayer, With<Player>>,
+    q_dream_block: Query<(&GlobalTransform, &Collider), With<DreamBlock>>,
+    player_state: Res<PlayerState>,
+    mut player_dash_available: ResMut<PlayerDashAvailable>,
+    mut events: DreamDashEvents,
+    mut dream_dash_velocity: Local<Vec2>,
+) {
+    if q_player.is_empty() {
+        if *player_state == PlayerState::DreamDashing {
+            // 角色已死亡
+            events.dream_dash_over_ew.send_default();
+        }
+        return;
+    }
+    let (player_entity, transform, facing, mut velocity, mut gravity_scale, mut sprite) =
+        q_player.single_mut();
+    let player_pos = transform.translation.truncate();
+    let inside_dream_block = |pos: Vec2| {
+        q_dream_block
+            .iter()
+            .any(|(dream_block_transform, collider)| {
+                let half_extents = collider
+                    .as_cuboid()
+                    .map(|cuboid| cuboid.half_extents())
+                    .unwrap_or(Vec2::splat(TILE_SIZE / 2.0));
+                let offset = (pos - dream_block_transform.translation().truncate()).abs();
+                offset.x < half_extents.x && offset.y < half_extents.y
+            })
+    };
+
+    match *player_state {
+        PlayerState::Dashing if inside_dream_block(player_pos) => {
+            info!("Dream dash start");
+            *dream_dash_velocity = if velocity.linvel.length() > 0.1 {
+                velocity.linvel.normalize() * PLAYER_DASH_SPEED
+            } else if *facing == Facing::Left {
+                Vec2::new(-PLAYER_DASH_SPEED, 0.0)
+            } else {
+                Vec2::new(PLAYER_DASH_SPEED, 0.0)
+            };
+            sprite.color = PLAYER_DREAM_DASHING_COLOR;
+            events.dream_dash_start_ew.send_default();
+        }
+        PlayerState::DreamDashing => {
+            velocity.linvel = *dream_dash_velocity;
+            gravity_scale.0 = 0.0;
+            let direction = dream_dash_velocity.normalize_or_zero();
+            // 前方即将离开梦境方块时，若出口处是实体则死亡
+            if !inside_dream_block(player_pos + direction * TILE_SIZE / 2.0)
+                && rapier_context
+                    .cast_ray(
+                        player_pos,
+                        direction,
+                        TILE_SIZE / 2.0 + 0.5,
+                        true,
+                        QueryFilter::default()
+                            .exclude_collider(player_entity)
+                            .exclude_sensors()
+                            .groups(CollisionGroups::new(Group::ALL, !DREAM_BLOCK_GROUP)),
+                    )
+                    .is_some()
+            {
+                info!("Dream dash into solid");
+                events.kill_player_ew.send_default();
+                return;
+            }
+            if !inside_dream_block(player_pos) {
+                info!("Dream dash over");
+                gravity_scale.0 = PLAYER_GRAVITY_SCALE;
+                sprite.color = Color::default();
+                // 离开梦境方块后恢复冲刺
+                player_dash_available.0 = true;
+                events.dream_dash_over_ew.send_default();
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::{ldtk_fields::LdtkFieldsError, Level};
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::common::TILE_SIZE;
+
+// 读取LDtk自定义字段的辅助函数，字段本身通过bevy_ecs_ldtk的LdtkFields读取
+
+// 可以从LDtk枚举字段读取的类型
+pub trait LdtkEnum: Sized {
+    fn from_variant(variant: &str) -> Option<Self>;
+}
+
+// 字段所属的实体或关卡
+pub trait FieldOwner: LdtkFields {
+    // 用于错误信息
+    fn owner_name(&self) -> String;
+}
+
+impl FieldOwner for EntityInstance {
+    fn owner_name(&self) -> String {
+        format!("{}({})", self.identifier, self.iid)
+    }
+}
+
+impl FieldOwner for Level {
+    fn owner_name(&self) -> String {
+        self.identifier.clone()
+    }
+}
+
+// 字段不存在或为空时返回None，类型错误时输出错误并返回None
+pub fn optional_field<T>(owner: &impl FieldOwner, value: Result<T, LdtkFieldsError>) -> Option<T> {
+    match value {
+        Ok(value) => Some(value),
+        Err(LdtkFieldsError::FieldNotFound { .. } | LdtkFieldsError::UnexpectedNull { .. }) => None,
+        Err(error) => {
+            error!("{}: {}", owner.owner_name(), error);
+            None
+        }
+    }
+}
+
+// 读取枚举字段，值无法识别时输出错误并返回None
+pub fn optional_enum_field<T: LdtkEnum>(owner: &impl FieldOwner, identifier: &str) -> Option<T> {
+    let variant = optional_field(owner, owner.get_enum_field(identifier))?;
+    T::from_variant(variant).or_else(|| {
+        error!(
+            "{}: field \"{}\" has unknown value \"{}\"",
+            owner.owner_name(),
+            identifier,
+            variant
+        );
+        None
+    })
+}
+
+// 点数组字段转为相对实体的偏移，LDtk网格坐标y轴向下
+pub fn point_offsets(entity_instance: &EntityInstance, identifier: &str) -> Vec<Vec2> {
+    optional_field(
+        entity_instance,
+        entity_instance.get_maybe_points_field(identifier),
+    )
+    .into_iter()
+    .flatten()
+    .flatten()
+    .map(|point| {
+        Vec2::new(
+            (point.x - entity_instance.grid.x) as f32 * TILE_SIZE,
+            -(point.y - entity_instance.grid.y) as f32 * TILE_SIZE,
+        )
+    })
+    .collect()
+}
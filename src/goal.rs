@@ -0,0 +1,231 @@
+use bevy::color;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    collectible::StrawberryRecord,
+    common::{AnimationIndices, AnimationTimer, AppState, SPRITE_DUST_ORDER},
+    level::Player,
+};
+
+// 庆祝动画时长
+const CELEBRATION_TIME: f32 = 2.0;
+// 每帧生成彩带的数量
+const CONFETTI_PER_FRAME: usize = 2;
+// 彩带存在时间
+const CONFETTI_LIFETIME: f32 = 1.2;
+const CONFETTI_GRAVITY: f32 = 120.0;
+
+// 山顶旗帜，触碰后通关
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct Goal;
+
+// 通关事件
+#[derive(Debug, Clone, PartialEq, Event)]
+pub struct LevelCompleteEvent {
+    pub level_iid: String,
+    // 通关用时（秒）
+    pub time: f32,
+    pub deaths: u32,
+    pub strawberries: usize,
+}
+
+// 当前关卡统计，进入关卡时重置
+#[derive(Debug, Default, Resource)]
+pub struct LevelStats {
+    pub time: f32,
+    pub deaths: u32,
+}
+
+// 最近一次通关结果，用于结算界面
+#[derive(Debug, Default, Resource)]
+pub struct LevelResult {
+    pub time: f32,
+    pub deaths: u32,
+    pub strawberries: usize,
+    // 下一关的下标，None表示已是最后一关
+    pub next_level: Option<usize>,
+}
+
+#[derive(Debug, Resource)]
+pub struct CelebrationTimer(pub Timer);
+
+// 庆祝时飞出的彩带
+#[derive(Debug, Component)]
+pub struct Confetti {
+    pub velocity: Vec2,
+    pub timer: Timer,
+}
+
+pub fn reset_level_stats(mut level_stats: ResMut<LevelStats>) {
+    *level_stats = LevelStats::default();
+}
+
+pub fn level_stats_tick(mut level_stats: ResMut<LevelStats>, time: Res<Time>) {
+    level_stats.time += time.delta_secs();
+}
+
+// 通关时统计所在关卡的结果
+#[derive(SystemParam)]
+pub struct LevelProgress<'w, 's> {
+    q_parent: Query<'w, 's, &'static Parent>,
+    q_level: Query<'w, 's, &'static LevelIid>,
+    q_project: Query<'w, 's, &'static LdtkProjectHandle>,
+    ldtk_projects: Res<'w, Assets<LdtkProject>>,
+    level_stats: Res<'w, LevelStats>,
+    strawberry_record: Res<'w, StrawberryRecord>,
+}
+
+impl LevelProgress<'_, '_> {
+    // 实体所在关卡的iid
+    pub fn level_iid(&self, entity: Entity) -> String {
+        self.q_parent
+            .iter_ancestors(entity)
+            .find_map(|ancestor| self.q_level.get(ancestor).ok())
+            .map(|level_iid| level_iid.get().clone())
+            .unwrap_or_default()
+    }
+
+    // 下一关的下标，None表示已是最后一关
+    pub fn next_level(&self, level_iid: &str) -> Option<usize> {
+        let ldtk_project = self
+            .q_project
+            .get_single()
+            .ok()
+            .and_then(|handle| self.ldtk_projects.get(handle))?;
+        let index = ldtk_project
+            .iter_raw_levels()
+            .position(|level| level.iid == level_iid)?;
+        (index + 1 < ldtk_project.iter_raw_levels().count()).then_some(index + 1)
+    }
+}
+
+// 角色触碰旗帜
+pub fn goal_reach(
+    mut commands: Commands,
+    rapier_context: Single<&RapierContext>,
+    q_player: Query<Entity, With<Player>>,
+    q_goal: Query<(), With<Goal>>,
+    level_progress: LevelProgress,
+    mut level_complete_ew: EventWriter<LevelCompleteEvent>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    let Ok(player) = q_player.get_single() else {
+        return;
+    };
+    let Some(goal_entity) = rapier_context
+        .intersection_pairs_with(player)
+        .filter(|(_, _, intersecting)| *intersecting)
+        .map(|(entity1, entity2, _)| if entity1 == player { entity2 } else { entity1 })
+        .find(|entity| q_goal.contains(*entity))
+    else {
+        return;
+    };
+    info!("Level complete");
+    let level_iid = level_progress.level_iid(goal_entity);
+    let next_level = level_progress.next_level(&level_iid);
+    let strawberries = level_progress.strawberry_record.count(&level_iid);
+    let level_stats = &level_progress.level_stats;
+
+    commands.insert_resource(LevelResult {
+        time: level_stats.time,
+        deaths: level_stats.deaths,
+        strawberries,
+        next_level,
+    });
+    commands.insert_resource(CelebrationTimer(Timer::from_seconds(
+        CELEBRATION_TIME,
+        TimerMode::Once,
+    )));
+    level_complete_ew.send(LevelCompleteEvent {
+        level_iid,
+        time: level_stats.time,
+        deaths: level_stats.deaths,
+        strawberries,
+    });
+    app_state.set(AppState::LevelComplete);
+}
+
+// 庆祝期间角色静止
+pub fn freeze_player(mut q_player: Query<(&mut Velocity, &mut GravityScale), With<Player>>) {
+    for (mut velocity, mut gravity_scale) in &mut q_player {
+        velocity.linvel = Vec2::ZERO;
+        gravity_scale.0 = 0.0;
+    }
+}
+
+// 从旗帜处喷出彩带，结束后进入结算界面
+pub fn celebrate(
+    mut commands: Commands,
+    q_goal: Query<&GlobalTransform, With<Goal>>,
+    mut q_confetti: Query<(Entity, &mut Confetti, &mut Transform, &mut Sprite)>,
+    mut celebration_timer: ResMut<CelebrationTimer>,
+    mut app_state: ResMut<NextState<AppState>>,
+    time: Res<Time>,
+) {
+    celebration_timer.0.tick(time.delta());
+    if celebration_timer.0.finished() {
+        app_state.set(AppState::Results);
+        return;
+    }
+
+    let mut rng = rand::thread_rng();
+    let colors = [
+        color::palettes::basic::RED,
+        color::palettes::basic::YELLOW,
+        color::palettes::basic::LIME,
+        color::palettes::basic::AQUA,
+        color::palettes::basic::FUCHSIA,
+    ];
+    for goal_transform in &q_goal {
+        for _ in 0..CONFETTI_PER_FRAME {
+            commands.spawn((
+                Confetti {
+                    velocity: Vec2::new(rng.gen_range(-60.0..60.0), rng.gen_range(60.0..140.0)),
+                    timer: Timer::from_seconds(CONFETTI_LIFETIME, TimerMode::Once),
+                },
+                Sprite {
+                    color: colors[rng.gen_range(0..colors.len())].into(),
+                    custom_size: Some(Vec2::splat(1.0)),
+                    ..default()
+                },
+                Transform::from_translation(
+                    goal_transform
+                        .translation()
+                        .truncate()
+                        .extend(SPRITE_DUST_ORDER),
+                ),
+            ));
+        }
+    }
+
+    for (entity, mut confetti, mut transform, mut sprite) in &mut q_confetti {
+        confetti.timer.tick(time.delta());
+        if confetti.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        confetti.velocity.y -= CONFETTI_GRAVITY * time.delta_secs();
+        transform.translation += (confetti.velocity * time.delta_secs()).extend(0.0);
+        sprite.color.set_alpha(confetti.timer.fraction_remaining());
+    }
+}
+
+// 旗帜飘动动画
+pub fn animate_goal_flag(
+    time: Res<Time>,
+    mut q_goal: Query<(&mut AnimationTimer, &mut AnimationIndices, &mut Sprite), With<Goal>>,
+) {
+    for (mut timer, mut indices, mut sprite) in &mut q_goal {
+        timer.0.tick(time.delta());
+        if timer.0.just_finished() {
+            if let Some(atlas) = &mut sprite.texture_atlas {
+                indices.index = (indices.index + 1) % indices.sprite_indices.len();
+                atlas.index = indices.sprite_indices[indices.index];
+            }
+        }
+    }
+}
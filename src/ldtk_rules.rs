@@ -0,0 +1,240 @@
+use bevy_ecs_ldtk::ldtk::FieldValue;
+use bevy_ecs_ldtk::prelude::*;
+
+// LDtk关卡规则，游戏和celeste-lint共用，这里不能依赖游戏的其他模块
+
+// 实体表，LDtk标识符 => main中注册的Bundle，`_`表示在spawn_ldtk_entity中处理
+// 游戏的注册代码和ENTITY_IDENTIFIERS都由这张表生成
+#[macro_export]
+macro_rules! ldtk_entities {
+    ($callback:ident) => {
+        $callback! {
+            Player => _,
+            WoodenStand => _,
+            Spring => SpringBundle,
+            Trap => TrapBundle,
+            Snowdrift => SnowdriftBundle,
+            BalloonRope => BalloonRopeBundle,
+            Strawberry => StrawberryBundle,
+            WingedStrawberry => WingedStrawberryBundle,
+            Key => KeyBundle,
+            LockedDoor => LockedDoorBundle,
+            MovingPlatform => MovingPlatformBundle,
+            ZipMover => ZipMoverBundle,
+            CrumblePlatform => CrumblePlatformBundle,
+            FallingBlock => FallingBlockBundle,
+            DreamBlock => DreamBlockBundle,
+            WindZone => WindZoneBundle,
+            Goal => GoalBundle,
+            CassetteBlock => CassetteBlockBundle,
+            TouchSwitch => TouchSwitchBundle,
+            SwitchGate => SwitchGateBundle,
+            Bumper => BumperBundle,
+            Seeker => SeekerBundle,
+            Trigger => TriggerBundle,
+        }
+    };
+}
+
+macro_rules! entity_identifiers {
+    ($($identifier:ident => $bundle:tt),* $(,)?) => {
+        &[$(stringify!($identifier)),*]
+    };
+}
+
+// main中注册或在spawn_ldtk_entity中处理的实体
+pub const ENTITY_IDENTIFIERS: &[&str] = ldtk_entities!(entity_identifiers);
+
+// 实体的必填字段
+pub const REQUIRED_FIELDS: &[(&str, &str)] = &[("ZipMover", "Path"), ("SwitchGate", "Path")];
+
+// 地形所在的IntGrid图层
+pub const TERRAIN_LAYER: &str = "Terrain";
+
+// 地形类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TerrainKind {
+    #[default]
+    Solid,
+    // 冰面
+    Ice,
+    // 无法攀爬的墙
+    NoClimb,
+    // 单向平台
+    OneWay,
+    // 岩浆
+    Lava,
+}
+// 地形表面属性
+#[derive(Debug, Clone, Copy)]
+pub struct TerrainProperties {
+    pub int_grid_value: i32,
+    pub kind: TerrainKind,
+    // 奔跑时每帧逼近目标速度的比例，1.0表示立即达到
+    pub traction: f32,
+    // 是否可以攀爬
+    pub climbable: bool,
+    // 是否只能从上方站立
+    pub one_way: bool,
+    // 触碰即死亡
+    pub deadly: bool,
+}
+
+// IntGrid值与地形属性对照表
+pub static TERRAIN_TABLE: [TerrainProperties; 5] = [
+    TerrainProperties {
+        int_grid_value: 1,
+        kind: TerrainKind::Solid,
+        traction: 1.0,
+        climbable: true,
+        one_way: false,
+        deadly: false,
+    },
+    TerrainProperties {
+        int_grid_value: 2,
+        kind: TerrainKind::Ice,
+        traction: 0.05,
+        climbable: true,
+        one_way: false,
+        deadly: false,
+    },
+    TerrainProperties {
+        int_grid_value: 3,
+        kind: TerrainKind::NoClimb,
+        traction: 1.0,
+        climbable: false,
+        one_way: false,
+        deadly: false,
+    },
+    TerrainProperties {
+        int_grid_value: 4,
+        kind: TerrainKind::OneWay,
+        traction: 1.0,
+        climbable: false,
+        one_way: true,
+        deadly: false,
+    },
+    TerrainProperties {
+        int_grid_value: 5,
+        kind: TerrainKind::Lava,
+        traction: 1.0,
+        climbable: false,
+        one_way: false,
+        deadly: true,
+    },
+];
+
+pub fn terrain_properties(int_grid_value: i32) -> Option<&'static TerrainProperties> {
+    TERRAIN_TABLE
+        .iter()
+        .find(|properties| properties.int_grid_value == int_grid_value)
+}
+
+impl TerrainKind {
+    pub fn properties(&self) -> &'static TerrainProperties {
+        TERRAIN_TABLE
+            .iter()
+            .find(|properties| properties.kind == *self)
+            .expect("Terrain kind missing from TERRAIN_TABLE")
+    }
+}
+
+// 空值或所有元素都为空的数组
+fn is_empty(value: &FieldValue) -> bool {
+    match value {
+        FieldValue::Int(value) => value.is_none(),
+        FieldValue::Float(value) => value.is_none(),
+        FieldValue::String(value) | FieldValue::FilePath(value) | FieldValue::Enum(value) => {
+            value.is_none()
+        }
+        FieldValue::Tile(value) => value.is_none(),
+        FieldValue::EntityRef(value) => value.is_none(),
+        FieldValue::Point(value) => value.is_none(),
+        FieldValue::Ints(values) => values.iter().all(Option::is_none),
+        FieldValue::Floats(values) => values.iter().all(Option::is_none),
+        FieldValue::Strings(values) | FieldValue::FilePaths(values) | FieldValue::Enums(values) => {
+            values.iter().all(Option::is_none)
+        }
+        FieldValue::Tiles(values) => values.iter().all(Option::is_none),
+        FieldValue::EntityRefs(values) => values.iter().all(Option::is_none),
+        FieldValue::Points(values) => values.iter().all(Option::is_none),
+        FieldValue::Bools(values) => values.is_empty(),
+        FieldValue::Colors(values) => values.is_empty(),
+        FieldValue::Bool(_) | FieldValue::Color(_) => false,
+    }
+}
+
+// 缺失或为空的必填字段
+pub fn missing_required_fields(entity_instance: &EntityInstance) -> Vec<&'static str> {
+    REQUIRED_FIELDS
+        .iter()
+        .filter(|(identifier, _)| *identifier == entity_instance.identifier)
+        .filter(|(_, field)| {
+            entity_instance
+                .field_instances
+                .iter()
+                .find(|field_instance| field_instance.identifier == *field)
+                .is_none_or(|field_instance| is_empty(&field_instance.value))
+        })
+        .map(|(_, field)| *field)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::IVec2;
+    use bevy_ecs_ldtk::ldtk::FieldInstance;
+
+    fn zip_mover(fields: Vec<(&str, FieldValue)>) -> EntityInstance {
+        EntityInstance {
+            identifier: "ZipMover".to_string(),
+            field_instances: fields
+                .into_iter()
+                .map(|(identifier, value)| FieldInstance {
+                    identifier: identifier.to_string(),
+                    tile: None,
+                    field_instance_type: String::new(),
+                    value,
+                    def_uid: 0,
+                    real_editor_values: Vec::new(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn path_with_points_is_present() {
+        let entity_instance = zip_mover(vec![(
+            "Path",
+            FieldValue::Points(vec![Some(IVec2::new(4, 2))]),
+        )]);
+        assert!(missing_required_fields(&entity_instance).is_empty());
+    }
+
+    #[test]
+    fn missing_path_is_reported() {
+        assert_eq!(
+            missing_required_fields(&zip_mover(Vec::new())),
+            vec!["Path"]
+        );
+    }
+
+    #[test]
+    fn empty_path_is_reported() {
+        for points in [Vec::new(), vec![None]] {
+            let entity_instance = zip_mover(vec![("Path", FieldValue::Points(points))]);
+            assert_eq!(missing_required_fields(&entity_instance), vec!["Path"]);
+        }
+    }
+
+    #[test]
+    fn other_entities_have_no_required_fields() {
+        let entity_instance = EntityInstance {
+            identifier: "Spring".to_string(),
+            ..Default::default()
+        };
+        assert!(missing_required_fields(&entity_instance).is_empty());
+    }
+}
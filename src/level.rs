@@ -2,21 +2,105 @@ use bevy::color;
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::{FRAC_PI_2, PI};
 
 use crate::{
+    bounds::RespawnPoint,
+    bumper::Bumper,
+    camera::CameraLock,
+    collectible::{Collectible, WingedStrawberry},
     common::{AnimationBundle, AnimationIndices, AnimationTimer, PLAYER_JUMP_SPEED, TILE_SIZE},
-    player::{spawn_dust, spawn_player, Facing},
+    dream_block::DreamBlock,
+    fields::{optional_enum_field, optional_field, LdtkEnum},
+    goal::{Confetti, Goal},
+    ldtk_rules::{self, terrain_properties, TerrainKind},
+    level_pack::LevelPack,
+    one_way::OneWayPlatform,
+    platform::{CrumblePlatform, FallingBlock, MovingPlatform, PlatformVelocity, ZipMover},
+    player::{spawn_dust, spawn_player, Dust, Facing, Hair, PlayerBounce},
+    rhythm::CassetteBlock,
+    seeker::Seeker,
     state_machine::PlayerState,
+    switch::{SwitchGate, TouchSwitch},
+    trigger::{set_targets_visible, Trigger},
+    wind::WindZone,
 };
 
 pub const LEVEL_TRANSLATION_OFFSET: Vec3 = Vec3::new(-250.0, -220.0, 0.0);
 
-// 陷阱
+// 陷阱（尖刺）
 #[derive(Debug, Component, Clone, Copy, Default)]
-pub struct Trap;
+pub struct Trap {
+    pub direction: TrapDirection,
+}
+// 尖刺朝向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrapDirection {
+    #[default]
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl TrapDirection {
+    // 尖刺指向
+    pub fn normal(&self) -> Vec2 {
+        match self {
+            TrapDirection::Up => Vec2::Y,
+            TrapDirection::Down => Vec2::NEG_Y,
+            TrapDirection::Left => Vec2::NEG_X,
+            TrapDirection::Right => Vec2::X,
+        }
+    }
+
+    pub fn rotation(&self) -> Quat {
+        match self {
+            TrapDirection::Up => Quat::IDENTITY,
+            TrapDirection::Down => Quat::from_rotation_z(PI),
+            TrapDirection::Left => Quat::from_rotation_z(FRAC_PI_2),
+            TrapDirection::Right => Quat::from_rotation_z(-FRAC_PI_2),
+        }
+    }
+}
 // 弹簧
 #[derive(Debug, Component, Clone, Copy, Default)]
-pub struct Spring;
+pub struct Spring {
+    pub orientation: SpringOrientation,
+    // 弹起速度
+    pub strength: f32,
+}
+// 弹簧朝向，LeftWall表示装在左侧墙上（向右弹）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpringOrientation {
+    #[default]
+    Up,
+    LeftWall,
+    RightWall,
+    Down,
+}
+
+impl SpringOrientation {
+    // 弹起方向
+    pub fn normal(&self) -> Vec2 {
+        match self {
+            SpringOrientation::Up => Vec2::Y,
+            SpringOrientation::LeftWall => Vec2::X,
+            SpringOrientation::RightWall => Vec2::NEG_X,
+            SpringOrientation::Down => Vec2::NEG_Y,
+        }
+    }
+
+    pub fn rotation(&self) -> Quat {
+        match self {
+            SpringOrientation::Up => Quat::IDENTITY,
+            SpringOrientation::LeftWall => Quat::from_rotation_z(-FRAC_PI_2),
+            SpringOrientation::RightWall => Quat::from_rotation_z(FRAC_PI_2),
+            SpringOrientation::Down => Quat::from_rotation_z(PI),
+        }
+    }
+}
 // 雪堆
 #[derive(Debug, Component, Clone, Copy, Default)]
 pub struct Snowdrift;
@@ -26,18 +110,57 @@ pub struct WoodenStand;
 // 气球绳
 #[derive(Debug, Component, Clone, Copy, Default)]
 pub struct BalloonRope;
-// 地形
+// 地形（合并后的碰撞体）
 #[derive(Debug, Component, Clone, Copy, Default)]
-pub struct Terrain;
+pub struct Terrain {
+    pub kind: TerrainKind,
+}
+// 地形格子，由spawn_terrain_collider合并成大的矩形碰撞体
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct TerrainCell {
+    pub kind: TerrainKind,
+}
 // 玩家
 #[derive(Debug, Component, Clone, Copy, Default)]
 pub struct Player;
+// 草莓
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct Strawberry;
+// 钥匙
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct Key;
+// 锁住的门，keys为可以打开此门的钥匙的iid，为空时任意钥匙都可打开
+#[derive(Debug, Component, Clone, Default)]
+pub struct LockedDoor {
+    pub keys: Vec<String>,
+}
+// 正在开锁的门
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct DoorUnlocking;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
 pub struct SpringUpEvent {
     entity: Entity,
 }
 
+// 房间重置事件，玩家复活时发送
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Event)]
+pub struct RoomResetEvent;
+
+// LDtk文件热重载状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HotReloadState {
+    #[default]
+    Idle,
+    // 等待关卡重新生成
+    Respawning,
+    // 关卡已生成，等待碰撞体就绪的帧数
+    Settling(u32),
+}
+
+#[derive(Debug, Default, Resource)]
+pub struct LevelHotReload(pub HotReloadState);
+
 #[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
 pub struct ColliderBundle {
     pub collider: Collider,
@@ -55,13 +178,13 @@ pub struct SensorBundle {
 
 #[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
 pub struct TerrainBundle {
-    pub terrain: Terrain,
     #[from_int_grid_cell]
-    pub collider_bundle: ColliderBundle,
+    pub terrain_cell: TerrainCell,
 }
 
 #[derive(Clone, Default, Bundle, LdtkEntity)]
 pub struct SpringBundle {
+    #[from_entity_instance]
     pub spring: Spring,
     #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 19)]
     sprite_sheet: Sprite,
@@ -73,6 +196,7 @@ pub struct SpringBundle {
 
 #[derive(Clone, Default, Bundle, LdtkEntity)]
 pub struct TrapBundle {
+    #[from_entity_instance]
     pub trap: Trap,
     #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 17)]
     sprite_sheet: Sprite,
@@ -86,6 +210,8 @@ pub struct WoodenStandBundle {
     sprite: Sprite,
     transform: Transform,
     collider_bundle: ColliderBundle,
+    one_way_platform: OneWayPlatform,
+    active_hooks: ActiveHooks,
 }
 
 #[derive(Clone, Default, Bundle, LdtkEntity)]
@@ -106,6 +232,171 @@ pub struct BalloonRopeBundle {
     animation_bundle: AnimationBundle,
 }
 
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct StrawberryBundle {
+    pub strawberry: Strawberry,
+    pub collectible: Collectible,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 26)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    sensor_bundle: SensorBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct WingedStrawberryBundle {
+    pub strawberry: Strawberry,
+    pub winged_strawberry: WingedStrawberry,
+    pub collectible: Collectible,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 28)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    sensor_bundle: SensorBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct KeyBundle {
+    pub key: Key,
+    pub collectible: Collectible,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 8)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    sensor_bundle: SensorBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct LockedDoorBundle {
+    pub locked_door: LockedDoor,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 20)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    pub collider_bundle: ColliderBundle,
+    #[from_entity_instance]
+    animation_bundle: AnimationBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct MovingPlatformBundle {
+    #[from_entity_instance]
+    pub moving_platform: MovingPlatform,
+    pub platform_velocity: PlatformVelocity,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 11)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    pub collider_bundle: ColliderBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct ZipMoverBundle {
+    #[from_entity_instance]
+    pub zip_mover: ZipMover,
+    pub platform_velocity: PlatformVelocity,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 12)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    pub collider_bundle: ColliderBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct CrumblePlatformBundle {
+    pub crumble_platform: CrumblePlatform,
+    pub platform_velocity: PlatformVelocity,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 23)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    pub collider_bundle: ColliderBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct FallingBlockBundle {
+    pub falling_block: FallingBlock,
+    pub platform_velocity: PlatformVelocity,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 64)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    pub collider_bundle: ColliderBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct DreamBlockBundle {
+    pub dream_block: DreamBlock,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 64)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    pub collider_bundle: ColliderBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct GoalBundle {
+    pub goal: Goal,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 118)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    animation_bundle: AnimationBundle,
+    #[from_entity_instance]
+    sensor_bundle: SensorBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct CassetteBlockBundle {
+    #[from_entity_instance]
+    pub cassette_block: CassetteBlock,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 64)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    pub collider_bundle: ColliderBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct TouchSwitchBundle {
+    #[from_entity_instance]
+    pub touch_switch: TouchSwitch,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 22)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    sensor_bundle: SensorBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct SwitchGateBundle {
+    pub switch_gate: SwitchGate,
+    pub platform_velocity: PlatformVelocity,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 64)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    pub collider_bundle: ColliderBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct BumperBundle {
+    #[from_entity_instance]
+    pub bumper: Bumper,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 22)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    sensor_bundle: SensorBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct SeekerBundle {
+    #[from_entity_instance]
+    pub seeker: Seeker,
+    #[sprite_sheet("textures/atlas.png", 8, 8, 16, 11, 0, 0, 21)]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    sensor_bundle: SensorBundle,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct TriggerBundle {
+    pub trigger: Trigger,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct WindZoneBundle {
+    #[from_entity_instance]
+    pub wind_zone: WindZone,
+}
+
 #[derive(Clone, Default, Bundle)]
 pub struct PlayerBundle {
     pub player: Player,
@@ -119,11 +410,57 @@ pub struct PlayerBundle {
     pub rotation_constraints: LockedAxes,
     pub velocity: Velocity,
     pub gravity_scale: GravityScale,
+    pub collision_groups: CollisionGroups,
+}
+
+impl LdtkEnum for SpringOrientation {
+    fn from_variant(variant: &str) -> Option<Self> {
+        match variant {
+            "Up" => Some(SpringOrientation::Up),
+            "LeftWall" => Some(SpringOrientation::LeftWall),
+            "RightWall" => Some(SpringOrientation::RightWall),
+            "Down" => Some(SpringOrientation::Down),
+            _ => None,
+        }
+    }
+}
+
+impl From<&EntityInstance> for Spring {
+    fn from(entity_instance: &EntityInstance) -> Spring {
+        Spring {
+            orientation: optional_enum_field(entity_instance, "Orientation")
+                .unwrap_or(SpringOrientation::Up),
+            strength: optional_field(entity_instance, entity_instance.get_float_field("Strength"))
+                .copied()
+                .unwrap_or(PLAYER_JUMP_SPEED),
+        }
+    }
+}
+
+impl LdtkEnum for TrapDirection {
+    fn from_variant(variant: &str) -> Option<Self> {
+        match variant {
+            "Up" => Some(TrapDirection::Up),
+            "Down" => Some(TrapDirection::Down),
+            "Left" => Some(TrapDirection::Left),
+            "Right" => Some(TrapDirection::Right),
+            _ => None,
+        }
+    }
+}
+
+impl From<&EntityInstance> for Trap {
+    fn from(entity_instance: &EntityInstance) -> Trap {
+        Trap {
+            direction: optional_enum_field(entity_instance, "Direction")
+                .unwrap_or(TrapDirection::Up),
+        }
+    }
 }
 
 impl From<&EntityInstance> for AnimationBundle {
     fn from(entity_instance: &EntityInstance) -> AnimationBundle {
-        match entity_instance.identifier.as_ref() {
+        let mut animation_bundle = match entity_instance.identifier.as_ref() {
             "BalloonRope" => AnimationBundle {
                 timer: AnimationTimer(Timer::from_seconds(0.3, TimerMode::Repeating)),
                 indices: AnimationIndices {
@@ -138,8 +475,34 @@ impl From<&EntityInstance> for AnimationBundle {
                     sprite_indices: vec![19, 18],
                 },
             },
+            "Goal" => AnimationBundle {
+                timer: AnimationTimer(Timer::from_seconds(0.2, TimerMode::Repeating)),
+                indices: AnimationIndices {
+                    index: 0,
+                    sprite_indices: vec![118, 119, 120],
+                },
+            },
+            "LockedDoor" => AnimationBundle {
+                timer: AnimationTimer(Timer::from_seconds(0.5, TimerMode::Once)),
+                indices: AnimationIndices {
+                    index: 0,
+                    sprite_indices: vec![20],
+                },
+            },
             _ => AnimationBundle::default(),
-        }
+        };
+        // 每帧时长可在LDtk中单独调整
+        let frame_time = optional_field(
+            entity_instance,
+            entity_instance.get_float_field("FrameTime"),
+        )
+        .copied()
+        .unwrap_or_else(|| animation_bundle.timer.0.duration().as_secs_f32());
+        animation_bundle
+            .timer
+            .0
+            .set_duration(std::time::Duration::from_secs_f32(frame_time.max(0.0)));
+        animation_bundle
     }
 }
 
@@ -152,6 +515,26 @@ impl From<&EntityInstance> for ColliderBundle {
                 restitution: Restitution::new(0.0),
                 active_events: ActiveEvents::COLLISION_EVENTS,
             },
+            "LockedDoor" | "DreamBlock" | "CassetteBlock" => ColliderBundle {
+                collider: Collider::cuboid(
+                    entity_instance.width as f32 / 2.0,
+                    entity_instance.height as f32 / 2.0,
+                ),
+                rigid_body: RigidBody::Fixed,
+                restitution: Restitution::new(0.0),
+                active_events: ActiveEvents::COLLISION_EVENTS,
+            },
+            "MovingPlatform" | "ZipMover" | "CrumblePlatform" | "FallingBlock" | "SwitchGate" => {
+                ColliderBundle {
+                    collider: Collider::cuboid(
+                        entity_instance.width as f32 / 2.0,
+                        entity_instance.height as f32 / 2.0,
+                    ),
+                    rigid_body: RigidBody::KinematicPositionBased,
+                    restitution: Restitution::new(0.0),
+                    active_events: ActiveEvents::COLLISION_EVENTS,
+                }
+            }
             _ => ColliderBundle::default(),
         }
     }
@@ -160,8 +543,28 @@ impl From<&EntityInstance> for ColliderBundle {
 impl From<&EntityInstance> for SensorBundle {
     fn from(entity_instance: &EntityInstance) -> SensorBundle {
         match entity_instance.identifier.as_ref() {
-            "Trap" | "Spring" => SensorBundle {
-                collider: Collider::cuboid(TILE_SIZE / 2.0, TILE_SIZE / 2.0),
+            // 只覆盖尖刺所在的下半格，随实体旋转到对应朝向
+            "Trap" => SensorBundle {
+                collider: Collider::compound(vec![(
+                    Vec2::new(0.0, -TILE_SIZE / 4.0),
+                    0.0,
+                    Collider::cuboid(TILE_SIZE / 2.0, TILE_SIZE / 4.0),
+                )]),
+                sensor: Sensor,
+                rotation_constraints: LockedAxes::ROTATION_LOCKED,
+                active_events: ActiveEvents::COLLISION_EVENTS,
+            },
+            "Spring" | "Strawberry" | "WingedStrawberry" | "Key" | "Goal" | "TouchSwitch" => {
+                SensorBundle {
+                    collider: Collider::cuboid(TILE_SIZE / 2.0, TILE_SIZE / 2.0),
+                    sensor: Sensor,
+                    rotation_constraints: LockedAxes::ROTATION_LOCKED,
+                    active_events: ActiveEvents::COLLISION_EVENTS,
+                }
+            }
+            // 圆形碰撞器
+            "Bumper" | "Seeker" => SensorBundle {
+                collider: Collider::ball(entity_instance.width.max(TILE_SIZE as i32) as f32 / 2.0),
                 sensor: Sensor,
                 rotation_constraints: LockedAxes::ROTATION_LOCKED,
                 active_events: ActiveEvents::COLLISION_EVENTS,
@@ -171,38 +574,185 @@ impl From<&EntityInstance> for SensorBundle {
     }
 }
 
-impl From<IntGridCell> for ColliderBundle {
-    fn from(int_grid_cell: IntGridCell) -> ColliderBundle {
-        if int_grid_cell.value == 1 {
-            ColliderBundle {
-                collider: Collider::cuboid(TILE_SIZE / 2.0, TILE_SIZE / 2.0),
-                rigid_body: RigidBody::Fixed,
-                restitution: Restitution::new(0.0),
-                active_events: ActiveEvents::COLLISION_EVENTS,
-            }
-        } else {
-            panic!("Unsupported int grid cell value")
+impl From<IntGridCell> for TerrainCell {
+    fn from(int_grid_cell: IntGridCell) -> TerrainCell {
+        let Some(properties) = terrain_properties(int_grid_cell.value) else {
+            warn!("Unsupported int grid cell value {}", int_grid_cell.value);
+            return TerrainCell::default();
+        };
+        TerrainCell {
+            kind: properties.kind,
         }
     }
 }
 
-pub fn setup_ldtk_world(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn setup_ldtk_world(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level_pack: Res<LevelPack>,
+) {
+    info!(
+        "Loading level pack {}, chapter {}",
+        level_pack.name,
+        level_pack.current().name
+    );
     commands.spawn(LdtkWorldBundle {
-        ldtk_handle: asset_server.load("levels.ldtk").into(),
+        ldtk_handle: asset_server
+            .load(level_pack.current().asset_path.clone())
+            .into(),
         transform: Transform::from_translation(Vec3::ZERO + LEVEL_TRANSLATION_OFFSET),
         ..Default::default()
     });
 }
 
+// 不挂在LDtk世界下的关卡实体
+type SpawnedOutsideWorld = (
+    Without<Parent>,
+    Or<(
+        With<Player>,
+        With<Hair>,
+        With<Dust>,
+        With<WoodenStand>,
+        With<Collectible>,
+        With<Confetti>,
+    )>,
+);
+
+// 离开关卡时清理LDtk世界以及不挂在世界下的实体
+pub fn cleanup_ldtk_world(
+    mut commands: Commands,
+    q_world: Query<Entity, With<LdtkProjectHandle>>,
+    q_spawned: Query<Entity, SpawnedOutsideWorld>,
+    mut camera_lock: ResMut<CameraLock>,
+    mut respawn_point: ResMut<RespawnPoint>,
+) {
+    camera_lock.0 = None;
+    respawn_point.0 = None;
+    for entity in q_world.iter().chain(q_spawned.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// 关卡生成后等待地形碰撞体合并并同步到物理世界的帧数
+const HOT_RELOAD_SETTLE_FRAMES: u32 = 2;
+// 寻找空位的最大搜索半径（格）
+const HOT_RELOAD_SEARCH_RADIUS: i32 = 8;
+
+// 热重载或切换关卡时需要清理的不挂在世界下的关卡实体
+pub type StaleAfterReload = (Without<Parent>, Or<(With<WoodenStand>, With<Collectible>)>);
+
+// levels.ldtk在磁盘上修改后，bevy_ecs_ldtk会重新生成关卡，
+// 这里清理不挂在世界下的关卡实体，角色保持原位
+pub fn ldtk_hot_reload(
+    mut commands: Commands,
+    mut ldtk_events: EventReader<AssetEvent<LdtkProject>>,
+    q_stale: Query<Entity, StaleAfterReload>,
+    mut hot_reload: ResMut<LevelHotReload>,
+) {
+    let modified = ldtk_events
+        .read()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }));
+    if !modified {
+        return;
+    }
+    info!("LDtk project modified, reloading level");
+    for entity in &q_stale {
+        commands.entity(entity).despawn_recursive();
+    }
+    hot_reload.0 = HotReloadState::Respawning;
+}
+
+// 重新生成期间冻结角色，生成完成后把角色移到最近的空位
+pub fn ldtk_hot_reload_settle(
+    rapier_context: Single<&RapierContext>,
+    mut level_events: EventReader<LevelEvent>,
+    mut q_player: Query<(Entity, &mut Transform, &mut Velocity), With<Player>>,
+    mut hot_reload: ResMut<LevelHotReload>,
+) {
+    let Ok((player_entity, mut transform, mut velocity)) = q_player.get_single_mut() else {
+        // 角色已死亡，复活时会重新放置，不再等待
+        if hot_reload.0 != HotReloadState::Idle {
+            hot_reload.0 = HotReloadState::Idle;
+        }
+        return;
+    };
+    let spawned = level_events
+        .read()
+        .any(|event| matches!(event, LevelEvent::Spawned(_)));
+    match hot_reload.0 {
+        HotReloadState::Idle => return,
+        HotReloadState::Respawning => {
+            if spawned {
+                hot_reload.0 = HotReloadState::Settling(HOT_RELOAD_SETTLE_FRAMES);
+            }
+        }
+        HotReloadState::Settling(0) => {
+            let pos = transform.translation.truncate();
+            let shape = Collider::ball(TILE_SIZE / 2.0 - 0.5);
+            let filter = QueryFilter::default()
+                .exclude_collider(player_entity)
+                .exclude_sensors();
+            let is_free = |candidate: Vec2| {
+                rapier_context
+                    .intersection_with_shape(candidate, 0.0, &shape, filter)
+                    .is_none()
+            };
+            if !is_free(pos) {
+                // 由近到远逐圈搜索
+                let free_pos = (1..=HOT_RELOAD_SEARCH_RADIUS).find_map(|radius| {
+                    let mut ring: Vec<Vec2> = (-radius..=radius)
+                        .flat_map(|x| (-radius..=radius).map(move |y| IVec2::new(x, y)))
+                        .filter(|offset| offset.x.abs() == radius || offset.y.abs() == radius)
+                        .map(|offset| pos + offset.as_vec2() * TILE_SIZE)
+                        .collect();
+                    ring.sort_by(|a, b| a.distance(pos).total_cmp(&b.distance(pos)));
+                    ring.into_iter().find(|candidate| is_free(*candidate))
+                });
+                match free_pos {
+                    Some(free_pos) => {
+                        info!("Player moved to free spot {:?} after reload", free_pos);
+                        transform.translation = free_pos.extend(transform.translation.z);
+                    }
+                    None => warn!("No free spot found for player after reload"),
+                }
+            }
+            hot_reload.0 = HotReloadState::Idle;
+        }
+        HotReloadState::Settling(frames) => {
+            hot_reload.0 = HotReloadState::Settling(frames - 1);
+        }
+    }
+    velocity.linvel = Vec2::ZERO;
+}
+
 pub fn spawn_ldtk_entity(
     mut commands: Commands,
     entity_query: Query<(Entity, &Transform, &EntityInstance), Added<EntityInstance>>,
     q_player: Query<(), With<Player>>,
+    q_entity_iid: Query<(Entity, &EntityIid)>,
+    mut q_sprite: Query<&mut Sprite>,
     mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     asset_server: Res<AssetServer>,
 ) {
-    for (_entity, transform, entity_instance) in entity_query.iter() {
+    if entity_query.is_empty() {
+        return;
+    }
+    let entities_by_iid: HashMap<&str, Entity> = q_entity_iid
+        .iter()
+        .map(|(entity, entity_iid)| (entity_iid.as_str(), entity))
+        .collect();
+    for (entity, transform, entity_instance) in entity_query.iter() {
         println!("{:?}, {:?}", entity_instance, transform.translation);
+        // 与celeste-lint相同的检查
+        if !ldtk_rules::ENTITY_IDENTIFIERS.contains(&entity_instance.identifier.as_str()) {
+            warn!("Unknown entity {}", entity_instance.identifier);
+        }
+        for field in ldtk_rules::missing_required_fields(entity_instance) {
+            error!(
+                "{}({}): missing field \"{}\"",
+                entity_instance.identifier, entity_instance.iid, field
+            );
+        }
         if entity_instance.identifier == *"WoodenStand" {
             let texture_handle = asset_server.load("textures/atlas.png");
             let mut atlas_layout = TextureAtlasLayout::new_empty(UVec2::new(128, 88));
@@ -232,8 +782,51 @@ pub fn spawn_ldtk_entity(
                     restitution: Restitution::new(0.),
                     active_events: ActiveEvents::COLLISION_EVENTS,
                 },
+                one_way_platform: OneWayPlatform,
+                active_hooks: ActiveHooks::MODIFY_SOLVER_CONTACTS,
             });
         }
+        if entity_instance.identifier == *"LockedDoor" {
+            // 门引用的钥匙，钥匙可能在门之后生成，这里只记录iid
+            let keys = entity_ref_iids(entity_instance, "Keys");
+            commands.entity(entity).insert(LockedDoor { keys });
+        }
+        if entity_instance.identifier == *"Trigger" {
+            // 解析spawn/despawn作用的实体
+            let targets = resolve_entity_refs(entity_instance, "Targets", &entities_by_iid);
+            let trigger = Trigger::new(entity_instance, targets);
+            // 需要由触发器生成的实体初始时隐藏
+            if trigger.targets_initially_hidden() {
+                set_targets_visible(&mut commands, &trigger.targets, false);
+            }
+            commands.entity(entity).insert(trigger);
+        }
+        if entity_instance.identifier == *"SwitchGate" {
+            // 解析门引用的开关
+            let switches = resolve_entity_refs(entity_instance, "Switches", &entities_by_iid);
+            commands
+                .entity(entity)
+                .insert(SwitchGate::new(entity_instance, switches));
+        }
+        if matches!(
+            entity_instance.identifier.as_str(),
+            "LockedDoor"
+                | "MovingPlatform"
+                | "ZipMover"
+                | "CrumblePlatform"
+                | "FallingBlock"
+                | "DreamBlock"
+                | "CassetteBlock"
+                | "SwitchGate"
+        ) {
+            // 可调整大小的实体，sprite拉伸至实体大小
+            if let Ok(mut sprite) = q_sprite.get_mut(entity) {
+                sprite.custom_size = Some(Vec2::new(
+                    entity_instance.width as f32,
+                    entity_instance.height as f32,
+                ));
+            }
+        }
         if entity_instance.identifier == *"Player" && q_player.is_empty() {
             spawn_player(
                 &mut commands,
@@ -245,26 +838,75 @@ pub fn spawn_ldtk_entity(
     }
 }
 
+// 把实体引用字段解析为已生成的实体
+fn resolve_entity_refs(
+    entity_instance: &EntityInstance,
+    identifier: &str,
+    entities_by_iid: &HashMap<&str, Entity>,
+) -> Vec<Entity> {
+    entity_ref_iids(entity_instance, identifier)
+        .iter()
+        .filter_map(|entity_iid| entities_by_iid.get(entity_iid.as_str()).copied())
+        .collect()
+}
+
+// 实体引用字段中的iid
+fn entity_ref_iids(entity_instance: &EntityInstance, identifier: &str) -> Vec<String> {
+    optional_field(
+        entity_instance,
+        entity_instance.get_maybe_entity_refs_field(identifier),
+    )
+    .into_iter()
+    .flatten()
+    .flatten()
+    .map(|entity_ref| entity_ref.entity_iid.clone())
+    .collect()
+}
+
+// 弹簧按朝向旋转sprite和碰撞体
+pub fn orient_spring(mut q_spring: Query<(&Spring, &mut Transform), Added<Spring>>) {
+    for (spring, mut transform) in &mut q_spring {
+        transform.rotation = spring.orientation.rotation();
+    }
+}
+
+// 尖刺按朝向旋转sprite和碰撞体
+pub fn orient_trap(mut q_trap: Query<(&Trap, &mut Transform), Added<Trap>>) {
+    for (trap, mut transform) in &mut q_trap {
+        transform.rotation = trap.direction.rotation();
+    }
+}
+
+// 侧向弹簧弹起后不覆盖水平速度的时间
+const SIDE_SPRING_CANNOT_MOVE_TIME: f32 = 0.2;
+
 // 弹簧弹起
 pub fn spring_up(
     mut collision_er: EventReader<CollisionEvent>,
-    q_spring: Query<Entity, With<Spring>>,
+    q_spring: Query<(Entity, &Spring)>,
     mut q_player: Query<&mut Velocity, With<Player>>,
     mut spring_up_ew: EventWriter<SpringUpEvent>,
+    mut player_bounce: PlayerBounce,
 ) {
     for event in collision_er.read() {
         match event {
             CollisionEvent::Started(entity1, entity2, _flags) => {
-                let spring_entity = if q_spring.contains(*entity1) {
-                    *entity1
-                } else if q_spring.contains(*entity2) {
-                    *entity2
+                let ((spring_entity, spring), other) = if let Ok(spring) = q_spring.get(*entity1) {
+                    (spring, *entity2)
+                } else if let Ok(spring) = q_spring.get(*entity2) {
+                    (spring, *entity1)
                 } else {
                     continue;
                 };
+                // 只有玩家会被弹起
+                let Ok(mut velocity) = q_player.get_mut(other) else {
+                    continue;
+                };
                 info!("Spring up");
-                for mut velocity in &mut q_player {
-                    velocity.linvel.y = PLAYER_JUMP_SPEED;
+                player_bounce.bounce(&mut velocity, spring.orientation.normal() * spring.strength);
+                if spring.orientation.normal().x != 0.0 {
+                    // 侧向弹簧
+                    player_bounce.lock_move(SIDE_SPRING_CANNOT_MOVE_TIME);
                 }
                 spring_up_ew.send(SpringUpEvent {
                     entity: spring_entity,
@@ -335,43 +977,155 @@ pub fn snowdrift_broken(
     }
 }
 
-// 木架
-pub fn wooden_stand_through(
+// 把同一图层中相连的同类地形格子合并成尽量大的矩形碰撞体，
+// 减少刚体数量，并避免角色在格子接缝处被卡住
+pub fn spawn_terrain_collider(
     mut commands: Commands,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    q_player: Query<&Transform, With<Player>>,
-    q_wooden_stand: Query<(), With<WoodenStand>>,
-    rapier_context: Single<&RapierContext>,
+    q_terrain_cell: Query<(&TerrainCell, &Transform, &Parent), Added<TerrainCell>>,
 ) {
-    if q_player.is_empty() {
+    if q_terrain_cell.is_empty() {
         return;
     }
-    let player_pos = q_player.single().translation.truncate();
-    let max_toi = 6.0;
-    if let Some((entity, _toi)) = rapier_context.cast_ray(
-        player_pos + Vec2::new(0., TILE_SIZE / 2. + 0.1),
-        Vec2::NEG_X,
-        max_toi,
-        true,
-        QueryFilter::default(),
-    ) {
-        if q_wooden_stand.contains(entity) {
-            commands.entity(entity).insert(Sensor);
-        }
-    } else if let Some((entity, _toi)) = rapier_context.cast_ray(
-        player_pos + Vec2::new(0., -TILE_SIZE / 2. - 0.1),
-        Vec2::X,
-        max_toi,
-        true,
-        QueryFilter::default(),
-    ) {
-        if q_wooden_stand.contains(entity) {
-            if keyboard_input.pressed(KeyCode::KeyS) && keyboard_input.pressed(KeyCode::KeyK) {
-                commands.entity(entity).insert(Sensor);
-            } else {
-                commands.entity(entity).remove::<Sensor>();
+    // 一行中连续的格子
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    struct Plate {
+        left: i32,
+        right: i32,
+    }
+    // 多行相同的Plate合并成矩形
+    #[derive(Debug, Clone, Copy)]
+    struct Rect {
+        left: i32,
+        right: i32,
+        bottom: i32,
+        top: i32,
+    }
+
+    // key为(图层, 地形类型)
+    let mut layer_cells: HashMap<(Entity, TerrainKind), HashSet<GridCoords>> = HashMap::new();
+    for (terrain_cell, transform, parent) in &q_terrain_cell {
+        // IntGrid格子位于图层中格子中心
+        let grid_coords = GridCoords::from(
+            (transform.translation.truncate() / TILE_SIZE)
+                .floor()
+                .as_ivec2(),
+        );
+        layer_cells
+            .entry((parent.get(), terrain_cell.kind))
+            .or_default()
+            .insert(grid_coords);
+    }
+
+    for ((layer_entity, kind), cells) in layer_cells {
+        let properties = kind.properties();
+        let (min_x, max_x) = (
+            cells.iter().map(|c| c.x).min().unwrap_or(0),
+            cells.iter().map(|c| c.x).max().unwrap_or(0),
+        );
+        let (min_y, max_y) = (
+            cells.iter().map(|c| c.y).min().unwrap_or(0),
+            cells.iter().map(|c| c.y).max().unwrap_or(0),
+        );
+
+        // 按行合并成Plate
+        let mut plate_rows: Vec<Vec<Plate>> = Vec::new();
+        for y in min_y..=max_y {
+            let mut row = Vec::new();
+            let mut plate_start = None;
+            // 多遍历一格，结束贴着右边界的Plate
+            for x in min_x..=max_x + 1 {
+                match (plate_start, cells.contains(&GridCoords { x, y })) {
+                    (Some(left), false) => {
+                        row.push(Plate { left, right: x - 1 });
+                        plate_start = None;
+                    }
+                    (None, true) => plate_start = Some(x),
+                    _ => {}
+                }
             }
+            plate_rows.push(row);
         }
+
+        let mut rects: Vec<Rect> = Vec::new();
+        if properties.one_way {
+            // 单向平台只合并同一行
+            for (i, row) in plate_rows.iter().enumerate() {
+                let y = min_y + i as i32;
+                rects.extend(row.iter().map(|plate| Rect {
+                    left: plate.left,
+                    right: plate.right,
+                    bottom: y,
+                    top: y,
+                }));
+            }
+        } else {
+            // 相邻行中完全相同的Plate向上延伸成矩形
+            let mut building: HashMap<Plate, Rect> = HashMap::new();
+            let mut prev_row: Vec<Plate> = Vec::new();
+            // 多加一个空行，结束贴着上边界的矩形
+            plate_rows.push(Vec::new());
+            for (i, row) in plate_rows.into_iter().enumerate() {
+                let y = min_y + i as i32;
+                for plate in &prev_row {
+                    if !row.contains(plate) {
+                        if let Some(rect) = building.remove(plate) {
+                            rects.push(rect);
+                        }
+                    }
+                }
+                for plate in &row {
+                    building
+                        .entry(*plate)
+                        .and_modify(|rect| rect.top = y)
+                        .or_insert(Rect {
+                            left: plate.left,
+                            right: plate.right,
+                            bottom: y,
+                            top: y,
+                        });
+                }
+                prev_row = row;
+            }
+        }
+
+        commands.entity(layer_entity).with_children(|layer| {
+            for rect in rects {
+                let half_width = (rect.right - rect.left + 1) as f32 * TILE_SIZE / 2.0;
+                let half_height = (rect.top - rect.bottom + 1) as f32 * TILE_SIZE / 2.0;
+                let center = Vec2::new(
+                    (rect.left + rect.right + 1) as f32 * TILE_SIZE / 2.0,
+                    (rect.bottom + rect.top + 1) as f32 * TILE_SIZE / 2.0,
+                );
+                let collider = if properties.one_way {
+                    // 单向平台只有顶部薄薄一层
+                    Collider::compound(vec![(
+                        Vec2::new(0.0, half_height - TILE_SIZE / 8.0),
+                        0.0,
+                        Collider::cuboid(half_width, TILE_SIZE / 8.0),
+                    )])
+                } else {
+                    Collider::cuboid(half_width, half_height)
+                };
+                let mut terrain = layer.spawn((
+                    Terrain { kind },
+                    ColliderBundle {
+                        collider,
+                        rigid_body: RigidBody::Fixed,
+                        restitution: Restitution::new(0.0),
+                        active_events: ActiveEvents::COLLISION_EVENTS,
+                    },
+                    Transform::from_translation(center.extend(0.0)),
+                ));
+                if properties.deadly {
+                    // 致死地形（岩浆）为传感器，由player_die检测
+                    terrain.insert(Sensor);
+                }
+                if properties.one_way {
+                    // 在物理求解前过滤接触点
+                    terrain.insert((OneWayPlatform, ActiveHooks::MODIFY_SOLVER_CONTACTS));
+                }
+            }
+        });
     }
 }
 
@@ -426,3 +1180,62 @@ pub fn aninmate_spring(
         }
     }
 }
+
+// 跟随玩家的钥匙碰到门时开锁
+pub fn key_open_door(
+    mut commands: Commands,
+    q_key: Query<(Entity, &EntityIid, &Collectible, &GlobalTransform), With<Key>>,
+    q_door: Query<(Entity, &LockedDoor, &GlobalTransform, &EntityInstance), Without<DoorUnlocking>>,
+    mut opened_doors: Local<Vec<Entity>>,
+) {
+    opened_doors.clear();
+    for (key_entity, key_iid, collectible, key_transform) in &q_key {
+        if !matches!(collectible, Collectible::Following(_)) {
+            continue;
+        }
+        let key_pos = key_transform.translation().truncate();
+        for (door_entity, locked_door, door_transform, entity_instance) in &q_door {
+            if opened_doors.contains(&door_entity) {
+                continue;
+            }
+            if !locked_door.keys.is_empty()
+                && !locked_door.keys.iter().any(|key| key == key_iid.as_str())
+            {
+                continue;
+            }
+            let half_size = Vec2::new(
+                entity_instance.width as f32 / 2.0 + TILE_SIZE / 2.0,
+                entity_instance.height as f32 / 2.0 + TILE_SIZE / 2.0,
+            );
+            let offset = (key_pos - door_transform.translation().truncate()).abs();
+            if offset.x <= half_size.x && offset.y <= half_size.y {
+                info!("Door unlocked");
+                commands.entity(key_entity).despawn_recursive();
+                commands
+                    .entity(door_entity)
+                    .insert(DoorUnlocking)
+                    .remove::<Collider>();
+                opened_doors.push(door_entity);
+                break;
+            }
+        }
+    }
+}
+
+// 开锁动画，逐渐淡出后移除门
+pub fn animate_door_unlock(
+    mut commands: Commands,
+    mut q_door: Query<(Entity, Ref<DoorUnlocking>, &mut AnimationTimer, &mut Sprite)>,
+    time: Res<Time>,
+) {
+    for (entity, door_unlocking, mut timer, mut sprite) in &mut q_door {
+        if door_unlocking.is_added() {
+            timer.0.reset();
+        }
+        timer.0.tick(time.delta());
+        sprite.color.set_alpha(timer.0.fraction_remaining());
+        if timer.0.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::Level;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    fields::{optional_enum_field, optional_field, LdtkEnum},
+    level_pack::LevelPack,
+    music::MusicChangeEvent,
+    weather::WeatherPreset,
+};
+
+// 死亡后的复活位置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RespawnRule {
+    // 从相邻关卡进入时在进入位置复活，否则在关卡起点
+    #[default]
+    Entrance,
+    // 总是在关卡中的Player实体处复活
+    Start,
+}
+
+impl LdtkEnum for RespawnRule {
+    fn from_variant(variant: &str) -> Option<Self> {
+        match variant {
+            "Entrance" => Some(RespawnRule::Entrance),
+            "Start" => Some(RespawnRule::Start),
+            _ => None,
+        }
+    }
+}
+
+// 当前关卡信息，来自LDtk关卡字段
+#[derive(Debug, Clone, Default, Resource)]
+pub struct LevelInfo {
+    pub name: String,
+    pub chapter: String,
+    // 目标通关时间（秒）
+    pub par_time: Option<f32>,
+    // 为None时保持当前音乐
+    pub music: Option<String>,
+    // 为None时保持当前天气
+    pub weather: Option<WeatherPreset>,
+    pub respawn: RespawnRule,
+}
+
+impl LevelInfo {
+    fn new(level: &Level, level_pack: &LevelPack) -> LevelInfo {
+        LevelInfo {
+            name: optional_field(level, level.get_string_field("Name"))
+                .cloned()
+                .unwrap_or_else(|| level.identifier.clone()),
+            chapter: optional_field(level, level.get_string_field("Chapter"))
+                .cloned()
+                .unwrap_or_else(|| level_pack.current().name.clone()),
+            par_time: optional_field(level, level.get_float_field("ParTime"))
+                .copied()
+                .filter(|par_time| *par_time > 0.0),
+            music: optional_field(level, level.get_string_field("Music"))
+                .filter(|music| !music.is_empty())
+                .cloned(),
+            weather: optional_enum_field(level, "Weather"),
+            respawn: optional_enum_field(level, "Respawn").unwrap_or_default(),
+        }
+    }
+}
+
+// 关卡生成后读取关卡字段，切换音乐和天气
+pub fn update_level_info(
+    mut level_events: EventReader<LevelEvent>,
+    q_project: Query<&LdtkProjectHandle>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
+    level_pack: Res<LevelPack>,
+    mut level_info: ResMut<LevelInfo>,
+    mut weather_preset: ResMut<WeatherPreset>,
+    mut music_change_ew: EventWriter<MusicChangeEvent>,
+) {
+    let Some(ldtk_project) = q_project
+        .get_single()
+        .ok()
+        .and_then(|handle| ldtk_projects.get(handle))
+    else {
+        return;
+    };
+    for event in level_events.read() {
+        let LevelEvent::Spawned(level_iid) = event else {
+            continue;
+        };
+        let Some(level) = ldtk_project
+            .iter_raw_levels()
+            .find(|level| level.iid == *level_iid.get())
+        else {
+            continue;
+        };
+        *level_info = LevelInfo::new(level, &level_pack);
+        info!("Level {} ({})", level_info.name, level_info.chapter);
+        if let Some(weather) = level_info.weather {
+            *weather_preset = weather;
+        }
+        if level_info.music.is_some() {
+            music_change_ew.send(MusicChangeEvent {
+                track: level_info.music.clone(),
+            });
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use bevy::asset::io::{AssetSourceBuilder, AssetSourceId};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use std::path::{Path, PathBuf};
+
+// 内置关卡
+const DEFAULT_LEVEL_FILE: &str = "levels.ldtk";
+// 关卡包目录中的清单文件
+const PACK_MANIFEST_FILE: &str = "pack.json";
+
+// 章节，对应一个.ldtk文件
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    pub name: String,
+    pub asset_path: String,
+    // 不在assets目录下的章节需要注册单独的资源源
+    source_dir: Option<PathBuf>,
+}
+
+// 关卡包，由命令行参数指定，默认只有内置关卡
+#[derive(Debug, Clone, Resource)]
+pub struct LevelPack {
+    pub name: String,
+    pub chapters: Vec<Chapter>,
+    pub current_chapter: usize,
+    // 从开始菜单进入游戏时的章节和关卡
+    pub start_chapter: usize,
+    pub start_level: LevelSelection,
+}
+
+impl Default for LevelPack {
+    fn default() -> Self {
+        LevelPack {
+            name: "Celeste".to_string(),
+            chapters: vec![Chapter {
+                name: "Celeste".to_string(),
+                asset_path: DEFAULT_LEVEL_FILE.to_string(),
+                source_dir: None,
+            }],
+            current_chapter: 0,
+            start_chapter: 0,
+            start_level: LevelSelection::index(0),
+        }
+    }
+}
+
+impl LevelPack {
+    // 解析命令行参数：[.ldtk文件或关卡包目录...] [--chapter 序号|名称] [--level 序号|标识符]
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<LevelPack, String> {
+        let mut paths = Vec::new();
+        let mut chapter = None;
+        let mut level = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--chapter" => {
+                    chapter = Some(args.next().ok_or("--chapter needs a value")?);
+                }
+                "--level" => {
+                    level = Some(args.next().ok_or("--level needs a value")?);
+                }
+                option if option.starts_with("--") => {
+                    return Err(format!("unknown option {}", option));
+                }
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        let mut level_pack = LevelPack::default();
+        if !paths.is_empty() {
+            level_pack.chapters.clear();
+            for path in &paths {
+                if path.is_dir() {
+                    let (name, chapters) = load_pack_dir(path)?;
+                    level_pack.name = name;
+                    level_pack.chapters.extend(chapters);
+                } else {
+                    if !path.exists() {
+                        return Err(format!("{}: no such file or directory", path.display()));
+                    }
+                    if path.extension().is_none_or(|extension| extension != "ldtk") {
+                        return Err(format!(
+                            "{}: expected a .ldtk file or a level pack directory",
+                            path.display()
+                        ));
+                    }
+                    level_pack.name = file_stem(path);
+                    level_pack
+                        .chapters
+                        .push(external_chapter(file_stem(path), path)?);
+                }
+            }
+            if paths.len() > 1 {
+                level_pack.name = "Custom".to_string();
+            }
+            if level_pack.chapters.is_empty() {
+                return Err("no .ldtk files found".to_string());
+            }
+        }
+        // 外部章节各自注册一个资源源，关卡引用的图片等资源相对.ldtk文件加载
+        for (index, chapter) in level_pack.chapters.iter_mut().enumerate() {
+            if chapter.source_dir.is_some() {
+                chapter.asset_path = format!("{}://{}", chapter_source(index), chapter.asset_path);
+            }
+        }
+
+        if let Some(chapter) = chapter {
+            level_pack.start_chapter = match chapter.parse::<usize>() {
+                Ok(index) => index,
+                Err(_) => level_pack
+                    .chapters
+                    .iter()
+                    .position(|candidate| candidate.name == chapter)
+                    .ok_or_else(|| format!("unknown chapter {}", chapter))?,
+            };
+            if level_pack.start_chapter >= level_pack.chapters.len() {
+                return Err(format!("chapter index {} out of range", chapter));
+            }
+        }
+        if let Some(level) = level {
+            level_pack.start_level = match level.parse::<usize>() {
+                Ok(index) => LevelSelection::index(index),
+                Err(_) => LevelSelection::Identifier(level),
+            };
+        }
+        level_pack.current_chapter = level_pack.start_chapter;
+        Ok(level_pack)
+    }
+
+    // 需要在添加DefaultPlugins之前调用
+    pub fn register_asset_sources(&self, app: &mut App) {
+        for (index, chapter) in self.chapters.iter().enumerate() {
+            let Some(source_dir) = &chapter.source_dir else {
+                continue;
+            };
+            app.register_asset_source(
+                AssetSourceId::Name(chapter_source(index).into()),
+                AssetSourceBuilder::platform_default(&source_dir.to_string_lossy(), None),
+            );
+        }
+    }
+
+    pub fn current(&self) -> &Chapter {
+        &self.chapters[self.current_chapter]
+    }
+
+    // 切换到下一章，已经是最后一章时返回false
+    pub fn advance_chapter(&mut self) -> bool {
+        if self.current_chapter + 1 < self.chapters.len() {
+            self.current_chapter += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn chapter_source(index: usize) -> String {
+    format!("chapter{}", index)
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+// 资源源使用绝对路径，不受工作目录影响
+fn external_chapter(name: String, path: &Path) -> Result<Chapter, String> {
+    let path =
+        std::fs::canonicalize(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    Ok(Chapter {
+        name,
+        asset_path: path
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        source_dir: path.parent().map(Path::to_path_buf),
+    })
+}
+
+// 读取关卡包目录，有清单时按清单排列章节，否则按文件名排列目录下的.ldtk文件
+//
+// 清单格式：{"name": "包名", "chapters": [{"name": "章节名", "file": "chapter1.ldtk"}]}
+fn load_pack_dir(dir: &Path) -> Result<(String, Vec<Chapter>), String> {
+    let manifest_path = dir.join(PACK_MANIFEST_FILE);
+    if !manifest_path.exists() {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map_err(|error| format!("{}: {}", dir.display(), error))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "ldtk")
+            })
+            .collect();
+        files.sort();
+        let chapters = files
+            .iter()
+            .map(|path| external_chapter(file_stem(path), path))
+            .collect::<Result<Vec<Chapter>, String>>()?;
+        return Ok((file_stem(dir), chapters));
+    }
+
+    let manifest_error = |message: &str| format!("{}: {}", manifest_path.display(), message);
+    let content = std::fs::read_to_string(&manifest_path)
+        .map_err(|error| manifest_error(&error.to_string()))?;
+    let manifest: serde_json::Value =
+        serde_json::from_str(&content).map_err(|error| manifest_error(&error.to_string()))?;
+    let name = manifest["name"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| file_stem(dir));
+    let chapters = manifest["chapters"]
+        .as_array()
+        .ok_or_else(|| manifest_error("\"chapters\" should be an array"))?
+        .iter()
+        .map(|chapter| {
+            let file = chapter["file"]
+                .as_str()
+                .ok_or_else(|| manifest_error("chapter is missing \"file\""))?;
+            let path = dir.join(file);
+            let name = chapter["name"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| file_stem(&path));
+            external_chapter(name, &path)
+        })
+        .collect::<Result<Vec<Chapter>, String>>()?;
+    Ok((name, chapters))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<LevelPack, String> {
+        LevelPack::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn asset(path: &str) -> String {
+        format!("{}/assets/{}", env!("CARGO_MANIFEST_DIR"), path)
+    }
+
+    // 测试用的临时关卡包目录，离开作用域时删除
+    struct TempPack(PathBuf);
+
+    impl TempPack {
+        fn new(name: &str, files: &[&str]) -> TempPack {
+            let dir = std::env::temp_dir().join(format!("celeste-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            for file in files {
+                std::fs::write(dir.join(file), "{}").unwrap();
+            }
+            TempPack(dir)
+        }
+
+        fn path(&self) -> String {
+            self.0.to_string_lossy().into_owned()
+        }
+    }
+
+    impl Drop for TempPack {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn no_args_uses_builtin_levels() {
+        let level_pack = parse(&[]).unwrap();
+        assert_eq!(level_pack.chapters.len(), 1);
+        assert_eq!(level_pack.current().asset_path, DEFAULT_LEVEL_FILE);
+        assert_eq!(level_pack.start_level, LevelSelection::index(0));
+    }
+
+    #[test]
+    fn ldtk_file_becomes_external_chapter() {
+        let level_pack = parse(&[&asset("levels.ldtk")]).unwrap();
+        assert_eq!(level_pack.name, "levels");
+        assert_eq!(level_pack.current().asset_path, "chapter0://levels.ldtk");
+    }
+
+    #[test]
+    fn directory_without_manifest_loads_ldtk_files() {
+        let pack = TempPack::new("no-manifest", &["b.ldtk", "a.ldtk", "notes.txt"]);
+        let level_pack = parse(&[&pack.path()]).unwrap();
+        let names: Vec<&str> = level_pack
+            .chapters
+            .iter()
+            .map(|chapter| chapter.name.as_str())
+            .collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(level_pack.name, file_stem(&pack.0));
+    }
+
+    #[test]
+    fn manifest_orders_chapters() {
+        let pack = TempPack::new("manifest", &["one.ldtk", "two.ldtk"]);
+        std::fs::write(
+            pack.0.join(PACK_MANIFEST_FILE),
+            r#"{"name": "Pack", "chapters": [{"name": "Second", "file": "two.ldtk"}, {"file": "one.ldtk"}]}"#,
+        )
+        .unwrap();
+        let level_pack = parse(&[&pack.path()]).unwrap();
+        assert_eq!(level_pack.name, "Pack");
+        let names: Vec<&str> = level_pack
+            .chapters
+            .iter()
+            .map(|chapter| chapter.name.as_str())
+            .collect();
+        assert_eq!(names, ["Second", "one"]);
+    }
+
+    #[test]
+    fn manifest_rejects_missing_chapter_file() {
+        let pack = TempPack::new("missing-chapter", &[]);
+        std::fs::write(
+            pack.0.join(PACK_MANIFEST_FILE),
+            r#"{"chapters": [{"file": "missing.ldtk"}]}"#,
+        )
+        .unwrap();
+        assert!(parse(&[&pack.path()]).is_err());
+    }
+
+    #[test]
+    fn chapter_source_dir_is_absolute() {
+        let level_pack = parse(&[&asset("../assets/levels.ldtk")]).unwrap();
+        let source_dir = level_pack.current().source_dir.clone().unwrap();
+        assert!(source_dir.is_absolute());
+        assert_eq!(source_dir, std::fs::canonicalize(asset("")).unwrap());
+    }
+
+    #[test]
+    fn chapter_and_level_options() {
+        let level_pack =
+            parse(&[&asset("levels.ldtk"), "--chapter", "levels", "--level", "2"]).unwrap();
+        assert_eq!(level_pack.start_chapter, 0);
+        assert_eq!(level_pack.start_level, LevelSelection::index(2));
+
+        let level_pack = parse(&["--level", "Level_1"]).unwrap();
+        assert_eq!(
+            level_pack.start_level,
+            LevelSelection::Identifier("Level_1".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_option() {
+        assert!(parse(&["--chapters", "1"]).is_err());
+        assert!(parse(&["--help"]).is_err());
+    }
+
+    #[test]
+    fn rejects_missing_option_value() {
+        assert!(parse(&["--chapter"]).is_err());
+        assert!(parse(&["--level"]).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_paths() {
+        assert!(parse(&[&asset("missing.ldtk")]).is_err());
+        assert!(parse(&[&format!("{}/Cargo.toml", env!("CARGO_MANIFEST_DIR"))]).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_chapter() {
+        assert!(parse(&["--chapter", "1"]).is_err());
+        assert!(parse(&["--chapter", "Summit"]).is_err());
+    }
+}
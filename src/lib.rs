@@ -0,0 +1,2 @@
+// 游戏和celeste-lint共用的模块
+pub mod ldtk_rules;
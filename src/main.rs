@@ -2,25 +2,66 @@ use bevy::image::ImageSamplerDescriptor;
 use bevy::{prelude::*, window::WindowResolution};
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
+use celeste::ldtk_rules;
 
+use bounds::*;
+use bumper::*;
 use camera::*;
+use collectible::*;
 use common::*;
+use dream_block::*;
+use goal::*;
+use ldtk_rules::*;
 use level::*;
+use level_info::*;
+use level_pack::*;
+use music::*;
+use one_way::*;
+use parallax::*;
+use platform::*;
 use player::*;
+use rhythm::*;
+use seeker::*;
 use state_machine::*;
+use switch::*;
+use trigger::*;
 use ui::*;
 use weather::*;
+use wind::*;
 
+mod bounds;
+mod bumper;
 mod camera;
+mod collectible;
 mod common;
+mod dream_block;
+mod fields;
+mod goal;
 mod level;
+mod level_info;
+mod level_pack;
+mod music;
+mod one_way;
+mod parallax;
+mod platform;
 mod player;
+mod rhythm;
+mod seeker;
 mod state_machine;
+mod switch;
+mod trigger;
 mod ui;
 mod weather;
+mod wind;
 
 fn main() {
+    // 命令行指定的关卡包，资源源需要在AssetPlugin之前注册
+    let level_pack = LevelPack::from_args(std::env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(2);
+    });
     let mut app = App::new();
+    level_pack.register_asset_sources(&mut app);
     app.add_plugins(
         DefaultPlugins
             .set(ImagePlugin {
@@ -38,7 +79,9 @@ fn main() {
                 ..default()
             }),
     )
-    .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
+    .add_plugins(RapierPhysicsPlugin::<OneWayPlatformHook>::pixels_per_meter(
+        100.0,
+    ))
     // .add_plugin(RapierDebugRenderPlugin::default())
     .add_plugins(LdtkPlugin);
 
@@ -47,21 +90,56 @@ fn main() {
         app.add_plugins(WeatherPlugin);
     }
 
+    for terrain_properties in TERRAIN_TABLE.iter() {
+        app.register_ldtk_int_cell_for_layer::<TerrainBundle>(
+            TERRAIN_LAYER,
+            terrain_properties.int_grid_value,
+        );
+    }
+
     app.init_state::<AppState>()
         .insert_resource(ClearColor(Color::BLACK))
-        .insert_resource(LevelSelection::index(0))
+        .insert_resource(level_pack.start_level.clone())
+        .insert_resource(level_pack)
         .insert_resource(PlayerState::Standing)
         .insert_resource(CameraState::Following)
         .insert_resource(PlayerGrounded(false))
         .insert_resource(PlayerNextTo(None))
+        .insert_resource(PlayerSurface(TerrainKind::Solid))
         .insert_resource(PlayerCannotMoveTime(0.0))
+        .insert_resource(PlayerDashAvailable(true))
+        .insert_resource(PlayerRiding(None))
+        .init_resource::<Wind>()
+        .insert_resource(StrawberryRecord::load())
+        .init_resource::<LevelStats>()
+        .init_resource::<LevelResult>()
+        .init_resource::<BeatClock>()
+        .init_resource::<CameraLock>()
+        .init_resource::<GameFlags>()
+        .init_resource::<CurrentMusic>()
+        .init_resource::<WeatherPreset>()
+        .init_resource::<LevelHotReload>()
+        .init_resource::<RespawnPoint>()
+        .init_resource::<LevelTransition>()
+        .init_resource::<LevelInfo>()
         .register_type::<PlayerState>()
         .register_type::<PlayerGrounded>()
         .register_type::<PlayerNextTo>()
+        .register_type::<PlayerDashAvailable>()
         .add_event::<SpringUpEvent>()
         .add_event::<CameraShakeEvent>()
         .add_event::<DashStartEvent>()
         .add_event::<DashOverEvent>()
+        .add_event::<KillPlayerEvent>()
+        .add_event::<RoomResetEvent>()
+        .add_event::<DreamDashStartEvent>()
+        .add_event::<DreamDashOverEvent>()
+        .add_event::<StrawberryCollectedEvent>()
+        .add_event::<LevelCompleteEvent>()
+        .add_event::<BeatEvent>()
+        .add_event::<TriggerActionEvent>()
+        .add_event::<MusicChangeEvent>()
+        .add_event::<DialogueEvent>()
         .add_systems(Startup, (setup_camera,))
         // Start Menu
         .add_systems(OnEnter(AppState::StartMenu), (setup_start_menu,))
@@ -71,7 +149,10 @@ fn main() {
         )
         .add_systems(OnExit(AppState::StartMenu), (cleanup_start_menu,))
         // Gaming
-        .add_systems(OnEnter(AppState::Gaming), (setup_ldtk_world,))
+        .add_systems(
+            OnEnter(AppState::Gaming),
+            (setup_ldtk_world, setup_game_hud, reset_level_stats),
+        )
         .add_systems(
             PreUpdate,
             (spawn_ldtk_entity,).run_if(in_state(AppState::Gaming)),
@@ -79,28 +160,123 @@ fn main() {
         .add_systems(
             Update,
             (
-                spring_up,
-                snowdrift_broken,
-                wooden_stand_through,
-                aninmate_spring,
-                animate_balloon_rope,
+                (
+                    orient_spring,
+                    orient_trap,
+                    spawn_terrain_collider,
+                    spring_up.after(player_dash),
+                    snowdrift_broken,
+                    (player_drop_through, drop_through_tick),
+                    key_open_door,
+                    animate_door_unlock,
+                    aninmate_spring,
+                    animate_balloon_rope,
+                    (
+                        ldtk_hot_reload,
+                        ldtk_hot_reload_settle.after(ldtk_hot_reload),
+                    ),
+                    (
+                        setup_level_bounds,
+                        level_transition_settle.before(level_bounds_check),
+                        level_bounds_check
+                            .after(ldtk_hot_reload_settle)
+                            .before(player_die),
+                    ),
+                ),
                 camera_follow,
                 camera_shake,
+                (
+                    setup_parallax_layers,
+                    parallax_scroll.after(camera_follow).after(camera_shake),
+                ),
+                (
+                    collectible_touch,
+                    collectible_follow,
+                    strawberry_bank,
+                    save_strawberry_record.after(strawberry_bank),
+                    winged_strawberry_escape,
+                    despawn_collected_strawberry,
+                    update_strawberry_hud,
+                    update_level_name_hud,
+                ),
+                (
+                    moving_platform_move,
+                    zip_mover_move.after(player_riding_detect),
+                    crumble_platform_update.after(player_riding_detect),
+                    falling_block_update.after(player_riding_detect),
+                    falling_block_out_of_bounds.after(falling_block_update),
+                    setup_dream_block,
+                    dream_block_pass_through,
+                    player_dream_dash.after(player_dash),
+                    player_riding_detect,
+                    platform_carry_player.after(player_riding_detect),
+                    platform_jump_momentum
+                        .after(player_riding_detect)
+                        .after(player_jump),
+                ),
                 (
                     player_run,
                     player_move,
                     player_jump,
                     player_dash,
                     player_dash_over,
+                    player_dash_refill,
                     player_climb,
                     player_die,
                 ),
+                (
+                    wind_zone_cycle,
+                    wind_detect.after(wind_zone_cycle),
+                    player_wind
+                        .after(wind_detect)
+                        .after(player_run)
+                        .after(player_move),
+                ),
                 despawn_hair.after(player_die),
                 player_revive,
                 spawn_hair.after(player_revive),
                 handle_player_collision,
                 player_grounded_detect,
-                player_next_to_detect,
+                (player_next_to_detect, player_surface_detect),
+                (
+                    level_stats_tick,
+                    goal_reach.after(player_die),
+                    animate_goal_flag,
+                ),
+                (
+                    (bumper_bounce.after(player_dash), bumper_update),
+                    (
+                        trigger_reset,
+                        trigger_detect,
+                        trigger_run_actions.after(trigger_detect),
+                        update_level_info,
+                        change_music
+                            .after(trigger_run_actions)
+                            .after(update_level_info),
+                        show_dialogue.after(trigger_run_actions),
+                    ),
+                    (
+                        setup_seeker,
+                        behaviour_state_tick::<SeekerState>,
+                        seeker_behaviour.after(behaviour_state_tick::<SeekerState>),
+                        seeker_contact.after(seeker_behaviour).after(player_dash),
+                        seeker_reset,
+                    ),
+                    (
+                        setup_touch_switch,
+                        setup_switch_gate,
+                        touch_switch_activate,
+                        touch_switch_reset,
+                        switch_gate_update.after(touch_switch_activate),
+                    ),
+                    (
+                        beat_clock_tick,
+                        setup_cassette_block,
+                        cassette_block_update
+                            .after(beat_clock_tick)
+                            .after(setup_cassette_block),
+                    ),
+                ),
                 player_facing_update,
                 (
                     animate_run,
@@ -117,10 +293,36 @@ fn main() {
             PostUpdate,
             (player_state_machine,).run_if(in_state(AppState::Gaming)),
         )
-        .register_ldtk_int_cell::<TerrainBundle>(1)
-        .register_ldtk_entity::<SpringBundle>("Spring")
-        .register_ldtk_entity::<TrapBundle>("Trap")
-        .register_ldtk_entity::<SnowdriftBundle>("Snowdrift")
-        .register_ldtk_entity::<BalloonRopeBundle>("BalloonRope")
-        .run();
+        // Level Complete
+        .add_systems(OnEnter(AppState::LevelComplete), (freeze_player,))
+        .add_systems(
+            Update,
+            (celebrate, animate_goal_flag).run_if(in_state(AppState::LevelComplete)),
+        )
+        // Results
+        .add_systems(
+            OnEnter(AppState::Results),
+            (cleanup_ldtk_world, cleanup_game_hud, setup_results_screen),
+        )
+        .add_systems(
+            Update,
+            (results_continue,).run_if(in_state(AppState::Results)),
+        )
+        .add_systems(OnExit(AppState::Results), (cleanup_results_screen,));
+    register_ldtk_entities(&mut app);
+    app.run();
+}
+
+// 按ldtk_rules中的实体表注册LDtk实体
+macro_rules! register_ldtk_entities {
+    ($($identifier:ident => $bundle:tt),* $(,)?) => {
+        fn register_ldtk_entities(app: &mut App) {
+            $(register_ldtk_entities!(@register app, $identifier, $bundle);)*
+        }
+    };
+    (@register $app:ident, $identifier:ident, _) => {};
+    (@register $app:ident, $identifier:ident, $bundle:ident) => {
+        $app.register_ldtk_entity::<$bundle>(stringify!($identifier));
+    };
 }
+celeste::ldtk_entities!(register_ldtk_entities);
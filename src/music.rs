@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+
+// 背景音乐
+#[derive(Debug, Component)]
+pub struct Music;
+
+// 切换背景音乐，track为None时停止
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct MusicChangeEvent {
+    // assets/music下的文件名（不含扩展名）
+    pub track: Option<String>,
+}
+
+// 当前播放的曲目
+#[derive(Debug, Default, Resource)]
+pub struct CurrentMusic(pub Option<String>);
+
+pub fn change_music(
+    mut commands: Commands,
+    mut music_change_er: EventReader<MusicChangeEvent>,
+    q_music: Query<Entity, With<Music>>,
+    mut current_music: ResMut<CurrentMusic>,
+    asset_server: Res<AssetServer>,
+) {
+    let Some(event) = music_change_er.read().last() else {
+        return;
+    };
+    if event.track == current_music.0 {
+        return;
+    }
+    for entity in &q_music {
+        commands.entity(entity).despawn_recursive();
+    }
+    if let Some(track) = &event.track {
+        info!("Play music {}", track);
+        commands.spawn((
+            Music,
+            AudioPlayer::new(asset_server.load(format!("music/{}.ogg", track))),
+            PlaybackSettings::LOOP,
+        ));
+    }
+    current_music.0 = event.track.clone();
+}
@@ -0,0 +1,98 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::rapier::math::{Real, Vector};
+
+use crate::{common::TILE_SIZE, level::Player};
+
+// 下穿时忽略单向平台的时间
+const DROP_THROUGH_TIME: f32 = 0.25;
+// 允许碰撞的法线与竖直方向的最大夹角
+const ONE_WAY_ALLOWED_ANGLE: f32 = 0.1;
+
+// 单向平台，只从上方落下时产生碰撞
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct OneWayPlatform;
+
+// 正在下穿单向平台的物体，剩余时间
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct DropThrough(pub f32);
+
+// 在物理求解前过滤单向平台的接触点
+#[derive(SystemParam)]
+pub struct OneWayPlatformHook<'w, 's> {
+    q_one_way: Query<'w, 's, (), With<OneWayPlatform>>,
+    q_drop_through: Query<'w, 's, (), With<DropThrough>>,
+}
+
+impl BevyPhysicsHooks for OneWayPlatformHook<'_, '_> {
+    fn modify_solver_contacts(&self, context: ContactModificationContextView) {
+        let (collider1, collider2) = (context.collider1(), context.collider2());
+        // 法线从collider1指向collider2，平台在collider1时物体应在上方
+        let (other, allowed_normal) = if self.q_one_way.contains(collider1) {
+            (collider2, Vector::<Real>::y())
+        } else if self.q_one_way.contains(collider2) {
+            (collider1, -Vector::<Real>::y())
+        } else {
+            return;
+        };
+        if self.q_drop_through.contains(other) {
+            context.raw.solver_contacts.clear();
+            return;
+        }
+        // 从下方或侧面穿过时整段接触都被忽略，直到分离
+        context
+            .raw
+            .update_as_oneway_platform(&allowed_normal, ONE_WAY_ALLOWED_ANGLE);
+    }
+}
+
+// 还没有在下穿的角色
+type PlayerNotDropping = (With<Player>, Without<DropThrough>);
+
+// 站在单向平台上按下+跳跃时下穿
+pub fn player_drop_through(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    q_player: Query<(Entity, &Transform), PlayerNotDropping>,
+    q_one_way: Query<(), With<OneWayPlatform>>,
+    rapier_context: Single<&RapierContext>,
+) {
+    let Ok((player_entity, transform)) = q_player.get_single() else {
+        return;
+    };
+    if !(keyboard_input.pressed(KeyCode::KeyS) && keyboard_input.just_pressed(KeyCode::KeyK)) {
+        return;
+    }
+    let player_pos = transform.translation.truncate();
+    let standing_on_one_way = rapier_context
+        .cast_ray(
+            player_pos,
+            Vec2::NEG_Y,
+            TILE_SIZE / 2.0 + 1.0,
+            true,
+            QueryFilter::default()
+                .exclude_collider(player_entity)
+                .exclude_sensors(),
+        )
+        .is_some_and(|(entity, _toi)| q_one_way.contains(entity));
+    if standing_on_one_way {
+        info!("Drop through one-way platform");
+        commands
+            .entity(player_entity)
+            .insert(DropThrough(DROP_THROUGH_TIME));
+    }
+}
+
+pub fn drop_through_tick(
+    mut commands: Commands,
+    mut q_drop_through: Query<(Entity, &mut DropThrough)>,
+    time: Res<Time>,
+) {
+    for (entity, mut drop_through) in &mut q_drop_through {
+        drop_through.0 -= time.delta_secs();
+        if drop_through.0 <= 0.0 {
+            commands.entity(entity).remove::<DropThrough>();
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::fields::optional_field;
+
+// 位于LDtk关卡背景(z=0)与第一个图层(z=1)之间
+const PARALLAX_Z: f32 = 0.1;
+const PARALLAX_Z_STEP: f32 = 0.01;
+
+// 视差背景层，挂在关卡实体下
+#[derive(Debug, Component, Clone)]
+pub struct ParallaxLayer {
+    // 图片路径，相对.ldtk文件
+    pub image: String,
+    // 0为固定在屏幕上，1为与关卡一起移动
+    pub factor: f32,
+    pub tint: Color,
+    pub repeat_x: bool,
+    pub repeat_y: bool,
+    // 层的中心（关卡本地坐标）
+    pub anchor: Vec2,
+}
+
+impl ParallaxLayer {
+    // 解析"图片路径, factor=0.5, tint=#RRGGBB, repeat=x|y|xy|none"格式的字符串
+    pub fn parse(value: &str) -> Result<ParallaxLayer, String> {
+        let mut parts = value.split(',').map(str::trim);
+        let image = parts
+            .next()
+            .filter(|image| !image.is_empty())
+            .ok_or("missing image path")?;
+        let mut layer = ParallaxLayer {
+            image: image.to_string(),
+            factor: 0.0,
+            tint: Color::WHITE,
+            repeat_x: false,
+            repeat_y: false,
+            anchor: Vec2::ZERO,
+        };
+        for part in parts {
+            let (key, argument) = part
+                .split_once('=')
+                .map(|(key, argument)| (key.trim(), argument.trim()))
+                .ok_or_else(|| format!("expected key=value, got {:?}", part))?;
+            match key {
+                "factor" => {
+                    layer.factor = argument
+                        .parse()
+                        .map_err(|_| format!("invalid factor {:?}", argument))?;
+                }
+                "tint" => {
+                    layer.tint = Srgba::hex(argument)
+                        .map_err(|_| format!("invalid tint {:?}", argument))?
+                        .into();
+                }
+                "repeat" => {
+                    (layer.repeat_x, layer.repeat_y) = match argument {
+                        "x" => (true, false),
+                        "y" => (false, true),
+                        "xy" => (true, true),
+                        "none" => (false, false),
+                        _ => return Err(format!("invalid repeat {:?}", argument)),
+                    };
+                }
+                _ => return Err(format!("unknown key {:?}", key)),
+            }
+        }
+        Ok(layer)
+    }
+}
+
+// 关卡生成后按LDtk关卡字段Parallax生成背景层
+pub fn setup_parallax_layers(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    q_level: Query<(Entity, &LevelIid)>,
+    q_project: Query<&LdtkProjectHandle>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
+    asset_server: Res<AssetServer>,
+) {
+    let Ok(project_handle) = q_project.get_single() else {
+        return;
+    };
+    let Some(ldtk_project) = ldtk_projects.get(project_handle) else {
+        return;
+    };
+    for event in level_events.read() {
+        let LevelEvent::Transformed(level_iid) = event else {
+            continue;
+        };
+        let Some(level) = ldtk_project
+            .iter_raw_levels()
+            .find(|level| level.iid == *level_iid.get())
+        else {
+            continue;
+        };
+        let Some((level_entity, _)) = q_level.iter().find(|(_, iid)| *iid == level_iid) else {
+            continue;
+        };
+        // 关卡没有配置视差层时为空
+        let values: Vec<String> = optional_field(level, level.get_maybe_strings_field("Parallax"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .cloned()
+            .collect();
+        let level_center = Vec2::new(level.px_wid as f32, level.px_hei as f32) / 2.0;
+        for (index, value) in values.iter().enumerate() {
+            let mut layer = match ParallaxLayer::parse(value) {
+                Ok(layer) => layer,
+                Err(error) => {
+                    error!("{}: Parallax {:?}: {}", level.identifier, value, error);
+                    continue;
+                }
+            };
+            layer.anchor = level_center;
+            // 图片路径相对.ldtk文件，与LDtk中其他资源一致
+            let image_path = match project_handle.handle.path() {
+                Some(project_path) => match project_path.resolve_embed(&layer.image) {
+                    Ok(image_path) => image_path,
+                    Err(error) => {
+                        error!("{}: Parallax {:?}: {}", level.identifier, value, error);
+                        continue;
+                    }
+                },
+                None => layer.image.clone().into(),
+            };
+            let image_mode = if layer.repeat_x || layer.repeat_y {
+                SpriteImageMode::Tiled {
+                    tile_x: layer.repeat_x,
+                    tile_y: layer.repeat_y,
+                    stretch_value: 1.0,
+                }
+            } else {
+                SpriteImageMode::Auto
+            };
+            let child = commands
+                .spawn((
+                    Sprite {
+                        image: asset_server.load(image_path),
+                        color: layer.tint,
+                        image_mode,
+                        ..default()
+                    },
+                    Transform::from_translation(
+                        level_center.extend(PARALLAX_Z + index as f32 * PARALLAX_Z_STEP),
+                    ),
+                    layer,
+                ))
+                .id();
+            commands.entity(level_entity).add_child(child);
+        }
+    }
+}
+
+// 背景层随相机滚动，重复的背景层始终铺满画面
+pub fn parallax_scroll(
+    q_camera: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+    mut q_layer: Query<(&ParallaxLayer, &mut Transform, &mut Sprite, &Parent), Without<Camera>>,
+    q_level: Query<&GlobalTransform>,
+    images: Res<Assets<Image>>,
+) {
+    let Ok((camera_transform, projection)) = q_camera.get_single() else {
+        return;
+    };
+    // 相机可见范围，随窗口大小变化
+    let view_size = projection.area.size();
+    for (layer, mut transform, mut sprite, parent) in &mut q_layer {
+        let Ok(level_transform) = q_level.get(parent.get()) else {
+            continue;
+        };
+        // 相机在关卡本地坐标中的位置
+        let camera_pos =
+            camera_transform.translation.truncate() - level_transform.translation().truncate();
+        let mut pos = layer.anchor + (camera_pos - layer.anchor) * (1.0 - layer.factor);
+
+        if let Some(image) = images.get(&sprite.image) {
+            let tile_size = image.size().as_vec2();
+            // 按图片大小对齐到相机附近，铺满可见范围再多一圈
+            if layer.repeat_x {
+                pos.x += ((camera_pos.x - pos.x) / tile_size.x).round() * tile_size.x;
+            }
+            if layer.repeat_y {
+                pos.y += ((camera_pos.y - pos.y) / tile_size.y).round() * tile_size.y;
+            }
+            let size = Vec2::new(
+                if layer.repeat_x {
+                    view_size.x + tile_size.x * 2.0
+                } else {
+                    tile_size.x
+                },
+                if layer.repeat_y {
+                    view_size.y + tile_size.y * 2.0
+                } else {
+                    tile_size.y
+                },
+            );
+            if sprite.custom_size != Some(size) {
+                sprite.custom_size = Some(size);
+            }
+        }
+        transform.translation.x = pos.x;
+        transform.translation.y = pos.y;
+    }
+}
@@ -0,0 +1,699 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    bounds::{LevelBounds, LEVEL_KILL_MARGIN},
+    camera::CameraShakeEvent,
+    common::TILE_SIZE,
+    fields::{optional_enum_field, optional_field, point_offsets, LdtkEnum},
+    level::{Player, RoomResetEvent},
+    player::{KillPlayerEvent, NextToSomething, PlayerCannotMoveTime, PlayerNextTo},
+    state_machine::PlayerState,
+};
+
+// 移动平台默认速度
+const MOVING_PLATFORM_DEFAULT_SPEED: f32 = 30.0;
+// 冲刺平台默认冲出速度
+const ZIP_MOVER_DEFAULT_SPEED: f32 = 240.0;
+// 冲刺平台返回速度与冲出速度之比
+const ZIP_MOVER_RETURN_RATIO: f32 = 0.25;
+// 冲刺平台到达终点后停留时间
+const ZIP_MOVER_PAUSE_TIME: f32 = 0.5;
+// 跳离平台后保留平台速度的时间
+const PLATFORM_MOMENTUM_TIME: f32 = 0.2;
+// 崩塌平台被踩后抖动时间
+const CRUMBLE_SHAKE_TIME: f32 = 0.6;
+// 崩塌平台消失后重新出现的时间
+const CRUMBLE_RESPAWN_TIME: f32 = 2.0;
+// 掉落方块被触碰后抖动时间
+const FALLING_BLOCK_SHAKE_TIME: f32 = 0.3;
+// 掉落方块重力加速度
+const FALLING_BLOCK_GRAVITY: f32 = 600.0;
+// 掉落方块最大下落速度
+const FALLING_BLOCK_MAX_SPEED: f32 = 240.0;
+// 抖动时sprite偏移幅度（相对sprite大小）
+const SHAKE_ANCHOR_OFFSET: f32 = 0.05;
+
+// 缓动函数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadInOut,
+    SineInOut,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::SineInOut => -((std::f32::consts::PI * t).cos() - 1.0) / 2.0,
+        }
+    }
+}
+
+impl LdtkEnum for Easing {
+    fn from_variant(variant: &str) -> Option<Self> {
+        match variant {
+            "Linear" => Some(Easing::Linear),
+            "QuadIn" => Some(Easing::QuadIn),
+            "QuadInOut" => Some(Easing::QuadInOut),
+            "SineInOut" => Some(Easing::SineInOut),
+            _ => None,
+        }
+    }
+}
+
+// 平台沿路径运动的进度
+#[derive(Debug, Clone, Default)]
+pub struct PlatformPath {
+    // 路径点，相对初始位置，第一个点为初始位置
+    pub points: Vec<Vec2>,
+    pub speed: f32,
+    pub easing: Easing,
+    // 当前路段
+    pub segment: usize,
+    // 当前路段进度 0~1
+    pub progress: f32,
+    // 当前相对初始位置的偏移
+    pub offset: Vec2,
+}
+
+impl PlatformPath {
+    // 沿路径前进，返回本帧位移，到达路段终点时切换到下一路段
+    pub fn advance(&mut self, speed: f32, easing: Easing, delta: f32) -> Vec2 {
+        if self.points.len() < 2 {
+            return Vec2::ZERO;
+        }
+        let start = self.points[self.segment];
+        let end = self.points[(self.segment + 1) % self.points.len()];
+        let length = start.distance(end).max(0.01);
+        self.progress = (self.progress + speed * delta / length).min(1.0);
+        let offset = start.lerp(end, easing.apply(self.progress));
+        let movement = offset - self.offset;
+        self.offset = offset;
+        if self.progress >= 1.0 {
+            self.progress = 0.0;
+            self.segment = (self.segment + 1) % self.points.len();
+        }
+        movement
+    }
+}
+
+// 读取LDtk中Path/Speed/Easing字段
+impl From<&EntityInstance> for PlatformPath {
+    fn from(entity_instance: &EntityInstance) -> PlatformPath {
+        let mut points = vec![Vec2::ZERO];
+        points.extend(point_offsets(entity_instance, "Path"));
+        let default_speed = if entity_instance.identifier == *"ZipMover" {
+            ZIP_MOVER_DEFAULT_SPEED
+        } else {
+            MOVING_PLATFORM_DEFAULT_SPEED
+        };
+        PlatformPath {
+            points,
+            speed: optional_field(entity_instance, entity_instance.get_float_field("Speed"))
+                .copied()
+                .unwrap_or(default_speed),
+            easing: optional_enum_field(entity_instance, "Easing").unwrap_or_default(),
+            ..default()
+        }
+    }
+}
+
+// 来回移动的平台
+#[derive(Debug, Component, Clone, Default)]
+pub struct MovingPlatform {
+    pub path: PlatformPath,
+}
+
+impl From<&EntityInstance> for MovingPlatform {
+    fn from(entity_instance: &EntityInstance) -> MovingPlatform {
+        MovingPlatform {
+            path: PlatformPath::from(entity_instance),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ZipMoverState {
+    #[default]
+    Idle,
+    // 冲向终点
+    Launching,
+    // 到达终点后停留
+    Pausing(f32),
+    // 返回起点
+    Returning,
+}
+
+// 冲刺平台，玩家接触后沿路径冲向最后一个路径点，再直接返回起点
+#[derive(Debug, Component, Clone, Default)]
+pub struct ZipMover {
+    pub path: PlatformPath,
+    pub state: ZipMoverState,
+}
+
+impl From<&EntityInstance> for ZipMover {
+    fn from(entity_instance: &EntityInstance) -> ZipMover {
+        // 冲刺平台必须配置终点，缺少时由spawn_ldtk_entity报错
+        ZipMover {
+            path: PlatformPath::from(entity_instance),
+            state: ZipMoverState::Idle,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum CrumbleState {
+    #[default]
+    Solid,
+    // 被踩后抖动，值为剩余时间
+    Shaking(f32),
+    // 已消失，值为重新出现的剩余时间
+    Gone(f32),
+}
+
+// 崩塌平台，玩家站上后抖动并消失，一段时间后重新出现
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct CrumblePlatform {
+    pub state: CrumbleState,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FallingBlockState {
+    #[default]
+    Idle,
+    // 被触碰后抖动，值为剩余时间
+    Shaking(f32),
+    // 下落中，值为当前下落速度
+    Falling(f32),
+    Landed,
+    // 掉出关卡底部，隐藏直到房间重置
+    OutOfBounds,
+}
+
+// 掉落方块，玩家触碰后下落，砸到玩家时玩家死亡
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct FallingBlock {
+    pub state: FallingBlockState,
+    // 已下落的距离
+    pub fallen: f32,
+}
+
+// 平台当前速度，用于带动玩家
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct PlatformVelocity(pub Vec2);
+
+// 玩家站在/攀爬在哪个平台上
+#[derive(Debug, Default, Resource)]
+pub struct PlayerRiding(pub Option<Entity>);
+
+// 移动平台
+pub fn moving_platform_move(
+    mut q_platform: Query<(&mut MovingPlatform, &mut PlatformVelocity, &mut Transform)>,
+    time: Res<Time>,
+) {
+    if time.delta_secs() <= 0.0 {
+        return;
+    }
+    for (mut moving_platform, mut platform_velocity, mut transform) in &mut q_platform {
+        let path = &mut moving_platform.path;
+        let (speed, easing) = (path.speed, path.easing);
+        let movement = path.advance(speed, easing, time.delta_secs());
+        transform.translation += movement.extend(0.0);
+        platform_velocity.0 = movement / time.delta_secs();
+    }
+}
+
+// 冲刺平台
+pub fn zip_mover_move(
+    mut q_zip_mover: Query<(Entity, &mut ZipMover, &mut PlatformVelocity, &mut Transform)>,
+    player_riding: Res<PlayerRiding>,
+    mut camera_shake_ew: EventWriter<CameraShakeEvent>,
+    time: Res<Time>,
+) {
+    if time.delta_secs() <= 0.0 {
+        return;
+    }
+    for (entity, mut zip_mover, mut platform_velocity, mut transform) in &mut q_zip_mover {
+        let mut movement = Vec2::ZERO;
+        match zip_mover.state {
+            ZipMoverState::Idle => {
+                if player_riding.0 == Some(entity) {
+                    // 没有配置终点时不启动
+                    if zip_mover.path.points.len() < 2 {
+                        warn_once!("Zip mover without Path can't launch");
+                        continue;
+                    }
+                    info!("Zip mover launched");
+                    zip_mover.state = ZipMoverState::Launching;
+                }
+            }
+            ZipMoverState::Launching => {
+                let path = &mut zip_mover.path;
+                let (speed, easing) = (path.speed, path.easing);
+                movement = path.advance(speed, easing, time.delta_secs());
+                if path.segment == path.points.len() - 1 {
+                    camera_shake_ew.send_default();
+                    zip_mover.state = ZipMoverState::Pausing(ZIP_MOVER_PAUSE_TIME);
+                }
+            }
+            ZipMoverState::Pausing(remaining) => {
+                let remaining = remaining - time.delta_secs();
+                zip_mover.state = if remaining > 0.0 {
+                    ZipMoverState::Pausing(remaining)
+                } else {
+                    ZipMoverState::Returning
+                };
+            }
+            ZipMoverState::Returning => {
+                let path = &mut zip_mover.path;
+                let speed = path.speed * ZIP_MOVER_RETURN_RATIO;
+                movement = path.advance(speed, Easing::Linear, time.delta_secs());
+                if path.segment == 0 {
+                    zip_mover.state = ZipMoverState::Idle;
+                }
+            }
+        }
+        transform.translation += movement.extend(0.0);
+        platform_velocity.0 = movement / time.delta_secs();
+    }
+}
+
+// 检测玩家是否站在或攀爬在平台上
+pub fn player_riding_detect(
+    rapier_context: Single<&RapierContext>,
+    q_player: Query<(Entity, &Transform), With<Player>>,
+    q_platform: Query<(), With<PlatformVelocity>>,
+    player_state: Res<PlayerState>,
+    player_next_to: Res<PlayerNextTo>,
+    mut player_riding: ResMut<PlayerRiding>,
+) {
+    player_riding.0 = None;
+    if q_player.is_empty() {
+        return;
+    }
+    let (player_entity, transform) = q_player.single();
+    let player_pos = transform.translation.truncate();
+    let direction = if *player_state == PlayerState::Climbing {
+        match player_next_to.0 {
+            Some(NextToSomething::LeftNext) => Vec2::NEG_X,
+            Some(NextToSomething::RightNext) => Vec2::X,
+            None => return,
+        }
+    } else {
+        Vec2::NEG_Y
+    };
+    if let Some((entity, _toi)) = rapier_context.cast_ray(
+        player_pos,
+        direction,
+        TILE_SIZE / 2. + 1.0,
+        true,
+        QueryFilter::default().exclude_collider(player_entity),
+    ) {
+        if q_platform.contains(entity) {
+            player_riding.0 = Some(entity);
+        }
+    }
+}
+
+// 平台带动玩家
+pub fn platform_carry_player(
+    mut q_player: Query<&mut Transform, With<Player>>,
+    q_platform: Query<&PlatformVelocity>,
+    player_riding: Res<PlayerRiding>,
+    time: Res<Time>,
+) {
+    if q_player.is_empty() {
+        return;
+    }
+    let Some(Ok(platform_velocity)) = player_riding.0.map(|entity| q_platform.get(entity)) else {
+        return;
+    };
+    let mut transform = q_player.single_mut();
+    transform.translation += (platform_velocity.0 * time.delta_secs()).extend(0.0);
+}
+
+// 跳离平台时继承平台速度
+pub fn platform_jump_momentum(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut q_player: Query<&mut Velocity, With<Player>>,
+    q_platform: Query<&PlatformVelocity>,
+    player_riding: Res<PlayerRiding>,
+    mut player_cannot_move_time: ResMut<PlayerCannotMoveTime>,
+) {
+    if q_player.is_empty() {
+        return;
+    }
+    if !keyboard_input.just_pressed(KeyCode::KeyK) || keyboard_input.pressed(KeyCode::KeyS) {
+        return;
+    }
+    let Some(Ok(platform_velocity)) = player_riding.0.map(|entity| q_platform.get(entity)) else {
+        return;
+    };
+    if platform_velocity.0 == Vec2::ZERO {
+        return;
+    }
+    let mut velocity = q_player.single_mut();
+    velocity.linvel += platform_velocity.0;
+    // 短时间内不覆盖水平速度，保留平台动量
+    player_cannot_move_time.0 = player_cannot_move_time.0.max(PLATFORM_MOMENTUM_TIME);
+}
+
+// 崩塌平台
+pub fn crumble_platform_update(
+    mut commands: Commands,
+    mut q_crumble: Query<(Entity, &mut CrumblePlatform, &mut Sprite, &mut Visibility)>,
+    player_riding: Res<PlayerRiding>,
+    mut room_reset_er: EventReader<RoomResetEvent>,
+    time: Res<Time>,
+) {
+    let room_reset = room_reset_er.read().next().is_some();
+    let mut rng = rand::thread_rng();
+    for (entity, mut crumble_platform, mut sprite, mut visibility) in &mut q_crumble {
+        match crumble_platform.state {
+            CrumbleState::Solid => {
+                if player_riding.0 == Some(entity) {
+                    info!("Crumble platform shaking");
+                    crumble_platform.state = CrumbleState::Shaking(CRUMBLE_SHAKE_TIME);
+                }
+            }
+            CrumbleState::Shaking(remaining) => {
+                let remaining = remaining - time.delta_secs();
+                if remaining > 0.0 {
+                    crumble_platform.state = CrumbleState::Shaking(remaining);
+                    sprite.anchor = Anchor::Custom(Vec2::new(
+                        rng.gen_range(-SHAKE_ANCHOR_OFFSET..SHAKE_ANCHOR_OFFSET),
+                        rng.gen_range(-SHAKE_ANCHOR_OFFSET..SHAKE_ANCHOR_OFFSET),
+                    ));
+                    // 逐渐碎裂
+                    if let Some(atlas) = &mut sprite.texture_atlas {
+                        atlas.index = 23 + ((1.0 - remaining / CRUMBLE_SHAKE_TIME) * 3.0) as usize;
+                    }
+                } else {
+                    crumble_platform.state = CrumbleState::Gone(CRUMBLE_RESPAWN_TIME);
+                    *visibility = Visibility::Hidden;
+                    commands.entity(entity).insert(ColliderDisabled);
+                }
+            }
+            CrumbleState::Gone(remaining) => {
+                let remaining = remaining - time.delta_secs();
+                crumble_platform.state = CrumbleState::Gone(remaining);
+            }
+        }
+        let respawn = match crumble_platform.state {
+            CrumbleState::Solid => false,
+            CrumbleState::Gone(remaining) => room_reset || remaining <= 0.0,
+            CrumbleState::Shaking(_) => room_reset,
+        };
+        if respawn {
+            crumble_platform.state = CrumbleState::Solid;
+            sprite.anchor = Anchor::Center;
+            if let Some(atlas) = &mut sprite.texture_atlas {
+                atlas.index = 23;
+            }
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<ColliderDisabled>();
+        }
+    }
+}
+
+// 掉落方块落地和砸到玩家时发送的事件
+#[derive(SystemParam)]
+pub struct FallingBlockEvents<'w> {
+    camera_shake_ew: EventWriter<'w, CameraShakeEvent>,
+    kill_player_ew: EventWriter<'w, KillPlayerEvent>,
+}
+
+type FallingBlockData = (
+    Entity,
+    &'static mut FallingBlock,
+    &'static mut PlatformVelocity,
+    &'static mut Transform,
+    &'static GlobalTransform,
+    &'static Collider,
+    &'static mut Sprite,
+    &'static mut Visibility,
+);
+
+// 掉落方块
+pub fn falling_block_update(
+    rapier_context: Single<&RapierContext>,
+    mut q_block: Query<FallingBlockData>,
+    q_player: Query<Entity, With<Player>>,
+    player_riding: Res<PlayerRiding>,
+    mut room_reset_er: EventReader<RoomResetEvent>,
+    mut events: FallingBlockEvents,
+    time: Res<Time>,
+) {
+    let room_reset = room_reset_er.read().next().is_some();
+    let mut rng = rand::thread_rng();
+    let player_entity = q_player.get_single().ok();
+    for (
+        entity,
+        mut falling_block,
+        mut platform_velocity,
+        mut transform,
+        global_transform,
+        collider,
+        mut sprite,
+        mut visibility,
+    ) in &mut q_block
+    {
+        platform_velocity.0 = Vec2::ZERO;
+        if room_reset {
+            // 回到初始位置
+            transform.translation.y += falling_block.fallen;
+            falling_block.fallen = 0.0;
+            falling_block.state = FallingBlockState::Idle;
+            sprite.anchor = Anchor::Center;
+            *visibility = Visibility::Inherited;
+            continue;
+        }
+        match falling_block.state {
+            FallingBlockState::Idle => {
+                // 站在上面、攀爬或从侧面、下方接触都会触发
+                let touched = player_riding.0 == Some(entity)
+                    || player_entity.is_some_and(|player_entity| {
+                        rapier_context
+                            .contact_pair(entity, player_entity)
+                            .is_some_and(|contact_pair| contact_pair.has_any_active_contact())
+                            || rapier_context.intersection_pair(entity, player_entity) == Some(true)
+                    });
+                if touched {
+                    info!("Falling block shaking");
+                    falling_block.state = FallingBlockState::Shaking(FALLING_BLOCK_SHAKE_TIME);
+                }
+            }
+            FallingBlockState::Shaking(remaining) => {
+                let remaining = remaining - time.delta_secs();
+                if remaining > 0.0 {
+                    falling_block.state = FallingBlockState::Shaking(remaining);
+                    sprite.anchor = Anchor::Custom(Vec2::new(
+                        rng.gen_range(-SHAKE_ANCHOR_OFFSET..SHAKE_ANCHOR_OFFSET),
+                        rng.gen_range(-SHAKE_ANCHOR_OFFSET..SHAKE_ANCHOR_OFFSET),
+                    ));
+                } else {
+                    sprite.anchor = Anchor::Center;
+                    falling_block.state = FallingBlockState::Falling(0.0);
+                }
+            }
+            FallingBlockState::Falling(speed) => {
+                let speed = (speed + FALLING_BLOCK_GRAVITY * time.delta_secs())
+                    .min(FALLING_BLOCK_MAX_SPEED);
+                let mut distance = speed * time.delta_secs();
+                let half_extents = collider
+                    .as_cuboid()
+                    .map(|cuboid| cuboid.half_extents())
+                    .unwrap_or(Vec2::splat(TILE_SIZE / 2.0));
+                let bottom = global_transform.translation().truncate()
+                    - Vec2::new(0.0, half_extents.y + 0.01);
+                // 从底部左中右三点向下检测
+                let mut landed = false;
+                for x in [-half_extents.x + 0.5, 0.0, half_extents.x - 0.5] {
+                    if let Some((hit_entity, toi)) = rapier_context.cast_ray(
+                        bottom + Vec2::new(x, 0.0),
+                        Vec2::NEG_Y,
+                        distance,
+                        true,
+                        QueryFilter::default()
+                            .exclude_collider(entity)
+                            .exclude_sensors(),
+                    ) {
+                        if q_player.contains(hit_entity) {
+                            // 砸到玩家
+                            events.kill_player_ew.send_default();
+                        } else {
+                            distance = toi;
+                            landed = true;
+                        }
+                    }
+                }
+                transform.translation.y -= distance;
+                falling_block.fallen += distance;
+                if landed {
+                    info!("Falling block landed");
+                    events.camera_shake_ew.send_default();
+                    falling_block.state = FallingBlockState::Landed;
+                } else {
+                    falling_block.state = FallingBlockState::Falling(speed);
+                    platform_velocity.0 = Vec2::new(0.0, -speed);
+                }
+            }
+            FallingBlockState::Landed | FallingBlockState::OutOfBounds => {}
+        }
+    }
+}
+
+// 下方没有地形时掉落方块会一直下落，掉出关卡底部后停下并隐藏
+pub fn falling_block_out_of_bounds(
+    mut q_block: Query<(
+        &mut FallingBlock,
+        &mut PlatformVelocity,
+        &mut Visibility,
+        &GlobalTransform,
+        &Collider,
+    )>,
+    q_level: Query<(&LevelBounds, &GlobalTransform)>,
+) {
+    let Ok((_, level_transform)) = q_level.get_single() else {
+        return;
+    };
+    let level_bottom = level_transform.translation().y;
+    for (mut falling_block, mut platform_velocity, mut visibility, global_transform, collider) in
+        &mut q_block
+    {
+        if !matches!(falling_block.state, FallingBlockState::Falling(_)) {
+            continue;
+        }
+        let half_height = collider
+            .as_cuboid()
+            .map(|cuboid| cuboid.half_extents().y)
+            .unwrap_or(TILE_SIZE / 2.0);
+        if global_transform.translation().y + half_height > level_bottom - LEVEL_KILL_MARGIN {
+            continue;
+        }
+        info!("Falling block left the level");
+        falling_block.state = FallingBlockState::OutOfBounds;
+        platform_velocity.0 = Vec2::ZERO;
+        *visibility = Visibility::Hidden;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASINGS: [Easing; 4] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadInOut,
+        Easing::SineInOut,
+    ];
+
+    fn path(points: &[Vec2]) -> PlatformPath {
+        PlatformPath {
+            points: points.to_vec(),
+            speed: 10.0,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn easing_endpoints() {
+        for easing in EASINGS {
+            assert!(easing.apply(0.0).abs() < 1e-6, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-6, "{:?}", easing);
+            // 超出范围时截断
+            assert!(easing.apply(-1.0).abs() < 1e-6, "{:?}", easing);
+            assert!((easing.apply(2.0) - 1.0).abs() < 1e-6, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn easing_midpoints() {
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert_eq!(Easing::QuadIn.apply(0.5), 0.25);
+        assert_eq!(Easing::QuadInOut.apply(0.25), 0.125);
+        assert_eq!(Easing::QuadInOut.apply(0.75), 0.875);
+        assert!((Easing::SineInOut.apply(0.5) - 0.5).abs() < 1e-6);
+        // 对称的缓动函数中点为0.5
+        for easing in [Easing::Linear, Easing::QuadInOut, Easing::SineInOut] {
+            assert!((easing.apply(0.5) - 0.5).abs() < 1e-6, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn easing_is_monotonic() {
+        for easing in EASINGS {
+            let mut last = easing.apply(0.0);
+            for i in 1..=100 {
+                let value = easing.apply(i as f32 / 100.0);
+                assert!(value >= last, "{:?}", easing);
+                last = value;
+            }
+        }
+    }
+
+    #[test]
+    fn easing_from_variant() {
+        for easing in EASINGS {
+            assert_eq!(Easing::from_variant(&format!("{:?}", easing)), Some(easing));
+        }
+        assert_eq!(Easing::from_variant("Bounce"), None);
+    }
+
+    #[test]
+    fn path_without_points_does_not_move() {
+        let mut platform_path = path(&[Vec2::ZERO]);
+        assert_eq!(platform_path.advance(10.0, Easing::Linear, 1.0), Vec2::ZERO);
+        assert_eq!(platform_path.segment, 0);
+    }
+
+    #[test]
+    fn path_reaches_segment_end_and_wraps() {
+        let end = Vec2::new(20.0, 0.0);
+        let mut platform_path = path(&[Vec2::ZERO, end]);
+        let movement = platform_path.advance(10.0, Easing::Linear, 1.0);
+        assert_eq!(movement, Vec2::new(10.0, 0.0));
+        assert_eq!(platform_path.segment, 0);
+
+        // 不会越过终点
+        let movement = platform_path.advance(10.0, Easing::Linear, 5.0);
+        assert_eq!(movement, Vec2::new(10.0, 0.0));
+        assert_eq!(platform_path.offset, end);
+        assert_eq!(platform_path.segment, 1);
+        assert_eq!(platform_path.progress, 0.0);
+
+        // 最后一个点之后返回起点
+        platform_path.advance(10.0, Easing::Linear, 5.0);
+        assert_eq!(platform_path.offset, Vec2::ZERO);
+        assert_eq!(platform_path.segment, 0);
+    }
+
+    #[test]
+    fn path_visits_every_point() {
+        let points = [Vec2::ZERO, Vec2::new(16.0, 0.0), Vec2::new(16.0, 16.0)];
+        let mut platform_path = path(&points);
+        for point in points.iter().cycle().skip(1).take(points.len()) {
+            platform_path.advance(16.0, Easing::QuadInOut, 10.0);
+            assert_eq!(platform_path.offset, *point);
+        }
+        assert_eq!(platform_path.segment, 0);
+    }
+}
@@ -1,18 +1,27 @@
 use bevy::color;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
 use std::collections::VecDeque;
 
 use crate::{
+    bounds::RespawnPoint,
     camera::CameraShakeEvent,
     common::{
         AnimationBundle, AnimationIndices, AnimationTimer, PLAYER_DASHING_COLOR, PLAYER_DASH_SPEED,
         PLAYER_GRAVITY_SCALE, PLAYER_JUMP_SPEED, PLAYER_RUN_SPEED, PLAYER_SLIDE_SPEED,
         SPRITE_DUST_ORDER, SPRITE_HAIR_ORDER, SPRITE_PLAYER_ORDER, TILE_SIZE,
     },
-    level::{Player, PlayerBundle, Snowdrift, Terrain, Trap, LEVEL_TRANSLATION_OFFSET},
+    goal::LevelStats,
+    ldtk_rules::TerrainKind,
+    level::{
+        Player, PlayerBundle, RoomResetEvent, Snowdrift, Terrain, Trap, LEVEL_TRANSLATION_OFFSET,
+    },
+    level_info::{LevelInfo, RespawnRule},
+    platform::PlatformVelocity,
     state_machine::PlayerState,
+    wind::{Wind, WIND_JUMPING_FACTOR, WIND_RUNNING_FACTOR},
 };
 
 // 脸朝向
@@ -34,9 +43,14 @@ pub struct Dust;
 // 冲刺开始事件
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Event)]
 pub struct DashStartEvent;
-// 冲刺结束事件
+// 冲刺结束事件，keep_velocity为true时表示冲刺被打断，保留当前速度
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Event)]
+pub struct DashOverEvent {
+    pub keep_velocity: bool,
+}
+// 杀死玩家事件（陷阱以外的死亡方式）
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Event)]
-pub struct DashOverEvent;
+pub struct KillPlayerEvent;
 
 // 角色是否在地面上
 #[derive(Debug, Default, Resource, Reflect)]
@@ -46,6 +60,45 @@ pub struct PlayerGrounded(pub bool);
 #[derive(Debug, Default, Resource)]
 pub struct PlayerCannotMoveTime(pub f32);
 
+// 角色是否还能冲刺（落地后恢复）
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct PlayerDashAvailable(pub bool);
+
+// 弹簧等机关弹开角色：打断冲刺并恢复冲刺次数
+#[derive(SystemParam)]
+pub struct PlayerBounce<'w> {
+    player_state: Res<'w, PlayerState>,
+    dash_over_ew: EventWriter<'w, DashOverEvent>,
+    player_dash_available: ResMut<'w, PlayerDashAvailable>,
+    player_cannot_move_time: ResMut<'w, PlayerCannotMoveTime>,
+}
+
+impl PlayerBounce<'_> {
+    pub fn dashing(&self) -> bool {
+        *self.player_state == PlayerState::Dashing
+    }
+
+    pub fn bounce(&mut self, velocity: &mut Velocity, linvel: Vec2) {
+        if self.dashing() {
+            self.dash_over_ew.send(DashOverEvent {
+                keep_velocity: true,
+            });
+        }
+        velocity.linvel = linvel;
+        self.player_dash_available.0 = true;
+    }
+
+    // 短时间内不覆盖水平速度
+    pub fn lock_move(&mut self, time: f32) {
+        self.player_cannot_move_time.0 = time;
+    }
+}
+
+// 角色脚下的地形
+#[derive(Debug, Default, Resource)]
+pub struct PlayerSurface(pub TerrainKind);
+
 // 角色是否挨着左边/右边的东西
 #[derive(Debug, Default, Resource, Reflect)]
 #[reflect(Resource)]
@@ -57,46 +110,74 @@ pub enum NextToSomething {
     RightNext,
 }
 
+// 生成角色和尘土所需的资源
+#[derive(SystemParam)]
+pub struct PlayerAssets<'w> {
+    atlas_layouts: ResMut<'w, Assets<TextureAtlasLayout>>,
+    asset_server: Res<'w, AssetServer>,
+}
+
+// 会杀死角色的陷阱和地形
+#[derive(SystemParam)]
+pub struct PlayerHazards<'w, 's> {
+    rapier_context: Single<'w, &'static RapierContext>,
+    q_trap: Query<'w, 's, &'static Trap>,
+    q_terrain: Query<'w, 's, &'static Terrain>,
+}
+
+impl PlayerHazards<'_, '_> {
+    pub fn touching(&self, player_entity: Entity, velocity: &Velocity) -> bool {
+        self.rapier_context
+            .intersection_pairs_with(player_entity)
+            .filter(|(_, _, intersecting)| *intersecting)
+            .any(|(collider1, collider2, _)| {
+                let other_entity = if collider1 == player_entity {
+                    collider2
+                } else {
+                    collider1
+                };
+                // 朝尖刺方向运动（或静止）时才会死亡
+                self.q_trap
+                    .get(other_entity)
+                    .is_ok_and(|trap| velocity.linvel.dot(trap.direction.normal()) <= 0.0)
+                    || self
+                        .q_terrain
+                        .get(other_entity)
+                        .is_ok_and(|terrain| terrain.kind.properties().deadly)
+            })
+    }
+}
+
 // 玩家死亡
 pub fn player_die(
     mut commands: Commands,
-    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-    asset_server: Res<AssetServer>,
-    mut collision_er: EventReader<CollisionEvent>,
+    mut player_assets: PlayerAssets,
+    mut kill_player_er: EventReader<KillPlayerEvent>,
     mut camera_shake_ew: EventWriter<CameraShakeEvent>,
-    q_trap: Query<Entity, With<Trap>>,
-    q_player: Query<&Transform, With<Player>>,
+    player_hazards: PlayerHazards,
+    q_player: Query<(Entity, &Transform, &Velocity), With<Player>>,
+    mut level_stats: ResMut<LevelStats>,
 ) {
-    for event in collision_er.read() {
-        match event {
-            CollisionEvent::Started(entity1, entity2, _flags) => {
-                if q_trap.contains(*entity1) {
-                    info!("Player died");
-                    commands.entity(*entity2).despawn_recursive();
-                    spawn_dust(
-                        &mut commands,
-                        &mut atlas_layouts,
-                        &asset_server,
-                        q_player.single().translation.truncate(),
-                        Color::default(),
-                    );
-                    camera_shake_ew.send_default();
-                } else if q_trap.contains(*entity2) {
-                    info!("Player died");
-                    commands.entity(*entity1).despawn_recursive();
-                    spawn_dust(
-                        &mut commands,
-                        &mut atlas_layouts,
-                        &asset_server,
-                        q_player.single().translation.truncate(),
-                        Color::default(),
-                    );
-                    camera_shake_ew.send_default();
-                }
-            }
-            _ => {}
-        }
+    let died = kill_player_er.read().next().is_some();
+    if q_player.is_empty() {
+        return;
+    }
+    let (player_entity, player_transform, velocity) = q_player.single();
+    let died = died || player_hazards.touching(player_entity, velocity);
+    if !died {
+        return;
     }
+    info!("Player died");
+    commands.entity(player_entity).despawn_recursive();
+    level_stats.deaths += 1;
+    spawn_dust(
+        &mut commands,
+        &mut player_assets.atlas_layouts,
+        &player_assets.asset_server,
+        player_transform.translation.truncate(),
+        Color::default(),
+    );
+    camera_shake_ew.send_default();
 }
 
 // 玩家复活
@@ -104,21 +185,31 @@ pub fn player_revive(
     mut commands: Commands,
     q_player: Query<(), With<Player>>,
     entity_query: Query<(&Transform, &EntityInstance)>,
-    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-    asset_server: Res<AssetServer>,
+    mut player_assets: PlayerAssets,
+    mut room_reset_ew: EventWriter<RoomResetEvent>,
+    respawn_point: Res<RespawnPoint>,
+    level_info: Res<LevelInfo>,
 ) {
-    if q_player.is_empty() {
-        for (transform, entity_instance) in &entity_query {
-            if entity_instance.identifier == *"Player" {
-                spawn_player(
-                    &mut commands,
-                    &mut atlas_layouts,
-                    &asset_server,
-                    (transform.translation + LEVEL_TRANSLATION_OFFSET).truncate(),
-                );
-                break;
-            }
-        }
+    if !q_player.is_empty() {
+        return;
+    }
+    let start = entity_query
+        .iter()
+        .find(|(_, entity_instance)| entity_instance.identifier == *"Player")
+        .map(|(transform, _)| (transform.translation + LEVEL_TRANSLATION_OFFSET).truncate());
+    // 从相邻关卡进入时默认在进入位置复活，没有进入位置或起点时使用另一个
+    let pos = match level_info.respawn {
+        RespawnRule::Entrance => respawn_point.0.or(start),
+        RespawnRule::Start => start.or(respawn_point.0),
+    };
+    if let Some(pos) = pos {
+        spawn_player(
+            &mut commands,
+            &mut player_assets.atlas_layouts,
+            &player_assets.asset_server,
+            pos,
+        );
+        room_reset_ew.send_default();
     }
 }
 
@@ -157,6 +248,7 @@ pub fn spawn_player(
         rotation_constraints: LockedAxes::ROTATION_LOCKED,
         velocity: Velocity::zero(),
         gravity_scale: GravityScale(PLAYER_GRAVITY_SCALE),
+        collision_groups: CollisionGroups::default(),
     });
 }
 
@@ -165,20 +257,30 @@ pub fn player_run(
     keyboard_input: Res<ButtonInput<KeyCode>>,
     mut q_player: Query<&mut Velocity, With<Player>>,
     player_state: Res<PlayerState>,
+    player_surface: Res<PlayerSurface>,
+    wind: Res<Wind>,
+    time: Res<Time>,
 ) {
     if q_player.is_empty() {
         return;
     }
     if *player_state == PlayerState::Running || *player_state == PlayerState::Standing {
         let mut velocity = q_player.single_mut();
-        if keyboard_input.pressed(KeyCode::KeyA) {
-            velocity.linvel.x = -PLAYER_RUN_SPEED;
+        let target_speed = if keyboard_input.pressed(KeyCode::KeyA) {
+            -PLAYER_RUN_SPEED
         } else if keyboard_input.pressed(KeyCode::KeyD) {
-            velocity.linvel.x = PLAYER_RUN_SPEED;
+            PLAYER_RUN_SPEED
         } else {
             // 不按键时停止左右奔跑
-            velocity.linvel.x = 0.0;
-        }
+            0.0
+        };
+        // 风力作为目标速度的偏移，不会在低摩擦地形上累加
+        let target_speed = target_speed + wind.0.x * WIND_RUNNING_FACTOR;
+        // 冰面等低摩擦地形上逐渐加减速
+        // traction为60帧每秒时每帧逼近的比例，按帧时间换算
+        let traction = player_surface.0.properties().traction;
+        let blend = 1.0 - (1.0 - traction).powf(time.delta_secs() * 60.0);
+        velocity.linvel.x += (target_speed - velocity.linvel.x) * blend;
     }
 }
 
@@ -189,6 +291,7 @@ pub fn player_move(
     player_state: Res<PlayerState>,
     player_next_to: Res<PlayerNextTo>,
     mut player_cannot_move_time: ResMut<PlayerCannotMoveTime>,
+    wind: Res<Wind>,
     time: Res<Time>,
 ) {
     if player_cannot_move_time.0 > 0.0 {
@@ -203,21 +306,27 @@ pub fn player_move(
     }
     if *player_state == PlayerState::Jumping || *player_state == PlayerState::Climbing {
         let mut velocity = q_player.single_mut();
+        // 风力作为目标速度的偏移，贴墙不重置速度时也不会累加
+        let wind_offset = if *player_state == PlayerState::Jumping {
+            wind.0.x * WIND_JUMPING_FACTOR
+        } else {
+            0.0
+        };
         if keyboard_input.pressed(KeyCode::KeyA) {
             if player_next_to.0.is_some() && player_next_to.0.unwrap() == NextToSomething::LeftNext
             {
             } else {
-                velocity.linvel.x = -PLAYER_RUN_SPEED;
+                velocity.linvel.x = -PLAYER_RUN_SPEED + wind_offset;
             }
         } else if keyboard_input.pressed(KeyCode::KeyD) {
             if player_next_to.0.is_some() && player_next_to.0.unwrap() == NextToSomething::RightNext
             {
             } else {
-                velocity.linvel.x = PLAYER_RUN_SPEED;
+                velocity.linvel.x = PLAYER_RUN_SPEED + wind_offset;
             }
         } else {
             // 不按键时停止左右移动
-            velocity.linvel.x = 0.0;
+            velocity.linvel.x = wind_offset;
         }
     }
 }
@@ -287,13 +396,18 @@ pub fn player_dash(
     mut dash_start_ew: EventWriter<DashStartEvent>,
     mut dash_over_ew: EventWriter<DashOverEvent>,
     player_state: Res<PlayerState>,
+    mut player_dash_available: ResMut<PlayerDashAvailable>,
     time: Res<Time>,
 ) {
     if q_player.is_empty() {
         return;
     }
     // 冲刺期间不能再次冲刺
-    if keyboard_input.just_pressed(KeyCode::KeyJ) && *player_state != PlayerState::Dashing {
+    if keyboard_input.just_pressed(KeyCode::KeyJ)
+        && *player_state != PlayerState::Dashing
+        && player_dash_available.0
+    {
+        player_dash_available.0 = false;
         *dash_timer = 0.2;
         dash_start_ew.send_default();
         camera_shake_ew.send_default();
@@ -338,12 +452,25 @@ pub fn player_dash_over(
         return;
     }
     let (mut velocity, mut gravity_scale) = q_player.single_mut();
-    if dash_over_er.read().next().is_some() {
-        velocity.linvel.x = 0.0;
+    if let Some(dash_over) = dash_over_er.read().last() {
+        if !dash_over.keep_velocity {
+            velocity.linvel.x = 0.0;
+        }
         gravity_scale.0 = PLAYER_GRAVITY_SCALE;
     }
 }
 
+// 落地后恢复冲刺
+pub fn player_dash_refill(
+    player_grounded: Res<PlayerGrounded>,
+    player_state: Res<PlayerState>,
+    mut player_dash_available: ResMut<PlayerDashAvailable>,
+) {
+    if player_grounded.0 && *player_state != PlayerState::Dashing {
+        player_dash_available.0 = true;
+    }
+}
+
 // 角色爬墙
 pub fn player_climb(
     mut q_player: Query<(&mut Velocity, &mut GravityScale), With<Player>>,
@@ -710,12 +837,21 @@ pub fn player_grounded_detect(
     last.0 = (pos.y * 10.).round();
 }
 
+// 可攀爬的地形和平台
+type ClimbableFilter = Or<(With<Terrain>, With<PlatformVelocity>)>;
+
 pub fn player_next_to_detect(
     rapier_context: Single<&RapierContext>,
     q_player: Query<&Transform, With<Player>>,
-    q_terrain: Query<&GlobalTransform, With<Terrain>>,
+    q_terrain: Query<Option<&Terrain>, ClimbableFilter>,
     mut player_next_to: ResMut<PlayerNextTo>,
 ) {
+    // 只有可攀爬的地形和平台才算挨着墙
+    let climbable = |entity: Entity| {
+        q_terrain
+            .get(entity)
+            .is_ok_and(|terrain| terrain.is_none_or(|terrain| terrain.kind.properties().climbable))
+    };
     if q_player.is_empty() {
         return;
     }
@@ -727,7 +863,7 @@ pub fn player_next_to_detect(
         true,
         QueryFilter::default(),
     ) {
-        if q_terrain.contains(entity) {
+        if climbable(entity) {
             player_next_to.0 = Some(NextToSomething::LeftNext);
         }
     } else if let Some((entity, _toi)) = rapier_context.cast_ray(
@@ -737,7 +873,7 @@ pub fn player_next_to_detect(
         true,
         QueryFilter::default(),
     ) {
-        if q_terrain.contains(entity) {
+        if climbable(entity) {
             player_next_to.0 = Some(NextToSomething::RightNext);
         }
     } else {
@@ -745,6 +881,31 @@ pub fn player_next_to_detect(
     }
 }
 
+pub fn player_surface_detect(
+    rapier_context: Single<&RapierContext>,
+    q_player: Query<(Entity, &Transform), With<Player>>,
+    q_terrain: Query<&Terrain>,
+    mut player_surface: ResMut<PlayerSurface>,
+) {
+    if q_player.is_empty() {
+        return;
+    }
+    let (player_entity, transform) = q_player.single();
+    player_surface.0 = rapier_context
+        .cast_ray(
+            transform.translation.truncate(),
+            Vec2::NEG_Y,
+            TILE_SIZE / 2. + 1.0,
+            true,
+            QueryFilter::default()
+                .exclude_collider(player_entity)
+                .exclude_sensors(),
+        )
+        .and_then(|(entity, _toi)| q_terrain.get(entity).ok())
+        .map(|terrain| terrain.kind)
+        .unwrap_or_default();
+}
+
 pub fn player_facing_update(mut q_player: Query<(&Velocity, &mut Facing), With<Player>>) {
     if q_player.is_empty() {
         return;
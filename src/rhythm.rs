@@ -0,0 +1,184 @@
+use bevy::color;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{common::TILE_SIZE, fields::optional_field, level::Player};
+
+// 默认节拍速度（每分钟拍数）
+const BEAT_CLOCK_DEFAULT_BPM: f32 = 120.0;
+// 卡带方块每隔多少拍切换一次
+const CASSETTE_BEATS_PER_SWAP: u32 = 2;
+// 卡带方块颜色组数
+const CASSETTE_SET_COUNT: u32 = 2;
+// 可穿过时的透明度
+const CASSETTE_PASSABLE_ALPHA: f32 = 0.25;
+// 即将变为实体时的透明度
+const CASSETTE_TELEGRAPH_ALPHA: f32 = 0.6;
+
+// 全局节拍时钟，音乐和其他定时实体可以同步到它
+#[derive(Debug, Resource)]
+pub struct BeatClock {
+    pub bpm: f32,
+    pub elapsed: f32,
+    // 已经过的拍数
+    pub beat: u32,
+    pub paused: bool,
+}
+
+impl Default for BeatClock {
+    fn default() -> Self {
+        BeatClock {
+            bpm: BEAT_CLOCK_DEFAULT_BPM,
+            elapsed: 0.0,
+            beat: 0,
+            paused: false,
+        }
+    }
+}
+
+impl BeatClock {
+    // 每拍时长（秒）
+    pub fn beat_duration(&self) -> f32 {
+        60.0 / self.bpm
+    }
+
+    // 当前拍已进行的比例，0.0~1.0
+    pub fn beat_progress(&self) -> f32 {
+        (self.elapsed / self.beat_duration()).fract()
+    }
+}
+
+// 每拍开始时发送
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Event)]
+pub struct BeatEvent {
+    pub beat: u32,
+}
+
+pub fn beat_clock_tick(
+    mut beat_clock: ResMut<BeatClock>,
+    mut beat_ew: EventWriter<BeatEvent>,
+    time: Res<Time>,
+) {
+    if beat_clock.paused || beat_clock.bpm <= 0.0 {
+        return;
+    }
+    beat_clock.elapsed += time.delta_secs();
+    let beat = (beat_clock.elapsed / beat_clock.beat_duration()) as u32;
+    while beat_clock.beat < beat {
+        beat_clock.beat += 1;
+        beat_ew.send(BeatEvent {
+            beat: beat_clock.beat,
+        });
+    }
+}
+
+// 卡带方块，同一组的方块一起在实体与可穿过之间切换
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct CassetteBlock {
+    // 所属颜色组
+    pub index: u32,
+    pub solid: bool,
+    // 该变为实体时角色还在方块内，等角色离开后再变
+    pub pending: bool,
+}
+
+impl From<&EntityInstance> for CassetteBlock {
+    fn from(entity_instance: &EntityInstance) -> CassetteBlock {
+        CassetteBlock {
+            index: optional_field(entity_instance, entity_instance.get_int_field("Index"))
+                .copied()
+                .unwrap_or(0)
+                .max(0) as u32
+                % CASSETTE_SET_COUNT,
+            solid: false,
+            pending: false,
+        }
+    }
+}
+
+impl CassetteBlock {
+    fn color(&self) -> Color {
+        match self.index {
+            0 => color::palettes::basic::FUCHSIA.into(),
+            _ => color::palettes::basic::AQUA.into(),
+        }
+    }
+}
+
+// 卡带方块生成时可穿过，由cassette_block_update按节拍变为实体
+pub fn setup_cassette_block(
+    mut commands: Commands,
+    q_cassette: Query<Entity, Added<CassetteBlock>>,
+) {
+    for entity in &q_cassette {
+        commands.entity(entity).insert(ColliderDisabled);
+    }
+}
+
+// 第beat拍时处于实体状态的颜色组
+fn cassette_active_set(beat: u32) -> u32 {
+    (beat / CASSETTE_BEATS_PER_SWAP) % CASSETTE_SET_COUNT
+}
+
+// 按节拍切换卡带方块
+pub fn cassette_block_update(
+    mut commands: Commands,
+    mut q_cassette: Query<(
+        Entity,
+        &mut CassetteBlock,
+        &GlobalTransform,
+        &Collider,
+        &mut Sprite,
+    )>,
+    q_player: Query<&Transform, With<Player>>,
+    beat_clock: Res<BeatClock>,
+    mut beat_er: EventReader<BeatEvent>,
+) {
+    let on_beat = beat_er.read().last().is_some();
+    let active_set = cassette_active_set(beat_clock.beat);
+    // 下一拍就要切换
+    let next_active_set = cassette_active_set(beat_clock.beat + 1);
+    let player_pos = q_player
+        .get_single()
+        .ok()
+        .map(|transform| transform.translation.truncate());
+
+    for (entity, mut cassette, transform, collider, mut sprite) in &mut q_cassette {
+        let should_be_solid = cassette.index == active_set;
+        // 在节拍上切换，新生成的方块和等待角色离开的方块每帧检查
+        let check = on_beat || cassette.is_added() || cassette.pending;
+        if check && should_be_solid && !cassette.solid {
+            let half_extents = collider
+                .as_cuboid()
+                .map(|cuboid| cuboid.half_extents())
+                .unwrap_or(Vec2::splat(TILE_SIZE / 2.0))
+                + Vec2::splat(TILE_SIZE / 2.0);
+            let overlapping = player_pos.is_some_and(|pos| {
+                let offset = (pos - transform.translation().truncate()).abs();
+                offset.x < half_extents.x && offset.y < half_extents.y
+            });
+            // 不在角色身上变成实体
+            cassette.pending = overlapping;
+            if !overlapping {
+                cassette.solid = true;
+                commands.entity(entity).remove::<ColliderDisabled>();
+            }
+        } else if check && !should_be_solid && (cassette.solid || cassette.pending) {
+            cassette.solid = false;
+            cassette.pending = false;
+            commands.entity(entity).insert(ColliderDisabled);
+        }
+
+        let alpha = if cassette.solid {
+            1.0
+        } else if cassette.index == next_active_set && !should_be_solid {
+            // 提前一拍预告，随节拍逐渐变实
+            CASSETTE_PASSABLE_ALPHA
+                + (CASSETTE_TELEGRAPH_ALPHA - CASSETTE_PASSABLE_ALPHA) * beat_clock.beat_progress()
+        } else {
+            CASSETTE_PASSABLE_ALPHA
+        };
+        sprite.color = cassette.color().with_alpha(alpha);
+    }
+}
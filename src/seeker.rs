@@ -0,0 +1,266 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    common::TILE_SIZE,
+    level::{Player, RoomResetEvent},
+    platform::PlatformPath,
+    player::{KillPlayerEvent, PlayerBounce},
+    state_machine::BehaviourState,
+};
+
+// 巡逻速度
+const SEEKER_PATROL_SPEED: f32 = 20.0;
+// 追击加速度与最大速度
+const SEEKER_CHASE_ACCELERATION: f32 = 200.0;
+const SEEKER_CHASE_MAX_SPEED: f32 = 90.0;
+// 视野距离
+const SEEKER_SIGHT_RANGE: f32 = TILE_SIZE * 10.0;
+// 丢失目标多久后放弃追击
+const SEEKER_LOSE_SIGHT_TIME: f32 = 1.0;
+// 被冲刺撞到后的眩晕时间
+const SEEKER_STUN_TIME: f32 = 1.0;
+// 被撞飞的速度
+const SEEKER_KNOCKBACK_SPEED: f32 = 150.0;
+// 眩晕时每秒速度衰减比例
+const SEEKER_STUN_DAMPING: f32 = 4.0;
+// 撞击后角色反弹速度
+const PLAYER_SEEKER_BOUNCE_SPEED: Vec2 = Vec2::new(80.0, 160.0);
+// 反弹后不能控制左右移动的时间
+const PLAYER_SEEKER_CANNOT_MOVE_TIME: f32 = 0.2;
+const SEEKER_COLOR: Color = Color::srgb(0.9, 0.2, 0.3);
+const SEEKER_STUNNED_COLOR: Color = Color::srgb(0.5, 0.5, 0.6);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SeekerState {
+    #[default]
+    Patrol,
+    Chase,
+    Stunned,
+}
+
+// 追踪者敌人
+#[derive(Debug, Component, Clone, Default)]
+pub struct Seeker {
+    // 巡逻点，相对初始位置
+    pub waypoints: Vec<Vec2>,
+    pub current_waypoint: usize,
+    // 初始位置（本地坐标），生成后设置
+    pub origin: Option<Vec3>,
+    pub velocity: Vec2,
+    // 上一次看到角色后经过的时间
+    pub lost_sight: f32,
+}
+
+impl From<&EntityInstance> for Seeker {
+    fn from(entity_instance: &EntityInstance) -> Seeker {
+        Seeker {
+            waypoints: PlatformPath::from(entity_instance).points,
+            ..default()
+        }
+    }
+}
+
+pub fn setup_seeker(
+    mut commands: Commands,
+    mut q_seeker: Query<(Entity, &mut Seeker, &Transform, &mut Sprite), Added<Seeker>>,
+) {
+    for (entity, mut seeker, transform, mut sprite) in &mut q_seeker {
+        seeker.origin = Some(transform.translation);
+        sprite.color = SEEKER_COLOR;
+        commands
+            .entity(entity)
+            .insert(BehaviourState::new(SeekerState::Patrol));
+    }
+}
+
+// 追踪者AI更新的组件
+type SeekerAi = (
+    Entity,
+    &'static mut Seeker,
+    &'static mut BehaviourState<SeekerState>,
+    &'static mut Transform,
+    &'static GlobalTransform,
+    &'static mut Sprite,
+);
+type PlayerNotSeeker = (With<Player>, Without<Seeker>);
+
+// 追踪者AI
+pub fn seeker_behaviour(
+    rapier_context: Single<&RapierContext>,
+    mut q_seeker: Query<SeekerAi>,
+    q_player: Query<(Entity, &Transform), PlayerNotSeeker>,
+    time: Res<Time>,
+) {
+    let player = q_player
+        .get_single()
+        .ok()
+        .map(|(entity, transform)| (entity, transform.translation.truncate()));
+    for (entity, mut seeker, mut behaviour, mut transform, global_transform, mut sprite) in
+        &mut q_seeker
+    {
+        let pos = global_transform.translation().truncate();
+        // 视线检测：射线第一个碰到的是角色
+        let sees_player = player.is_some_and(|(player_entity, player_pos)| {
+            let to_player = player_pos - pos;
+            to_player.length() < SEEKER_SIGHT_RANGE
+                && rapier_context
+                    .cast_ray(
+                        pos,
+                        to_player.normalize_or_zero(),
+                        SEEKER_SIGHT_RANGE,
+                        true,
+                        QueryFilter::default()
+                            .exclude_collider(entity)
+                            .exclude_sensors(),
+                    )
+                    .is_some_and(|(hit, _toi)| hit == player_entity)
+        });
+
+        match behaviour.current {
+            SeekerState::Patrol => {
+                if sees_player {
+                    info!("Seeker spotted player");
+                    behaviour.transition(SeekerState::Chase);
+                } else if let Some(origin) = seeker.origin {
+                    let target = origin.truncate()
+                        + seeker
+                            .waypoints
+                            .get(seeker.current_waypoint)
+                            .copied()
+                            .unwrap_or_default();
+                    let to_target = target - transform.translation.truncate();
+                    if to_target.length() < 1.0 {
+                        seeker.current_waypoint =
+                            (seeker.current_waypoint + 1) % seeker.waypoints.len().max(1);
+                    }
+                    seeker.velocity = to_target.normalize_or_zero() * SEEKER_PATROL_SPEED;
+                }
+            }
+            SeekerState::Chase => {
+                if sees_player {
+                    seeker.lost_sight = 0.0;
+                } else {
+                    seeker.lost_sight += time.delta_secs();
+                }
+                match player {
+                    Some((_, player_pos)) if seeker.lost_sight < SEEKER_LOSE_SIGHT_TIME => {
+                        let direction = (player_pos - pos).normalize_or_zero();
+                        seeker.velocity = (seeker.velocity
+                            + direction * SEEKER_CHASE_ACCELERATION * time.delta_secs())
+                        .clamp_length_max(SEEKER_CHASE_MAX_SPEED);
+                    }
+                    _ => {
+                        info!("Seeker lost player");
+                        seeker.lost_sight = 0.0;
+                        behaviour.transition(SeekerState::Patrol);
+                    }
+                }
+            }
+            SeekerState::Stunned => {
+                let damping = (1.0 - SEEKER_STUN_DAMPING * time.delta_secs()).max(0.0);
+                seeker.velocity *= damping;
+                if behaviour.elapsed >= SEEKER_STUN_TIME {
+                    behaviour.transition(SeekerState::Patrol);
+                }
+            }
+        }
+
+        // 碰到墙壁时停下
+        let movement = seeker.velocity * time.delta_secs();
+        if movement != Vec2::ZERO
+            && rapier_context
+                .cast_ray(
+                    pos,
+                    movement.normalize(),
+                    TILE_SIZE / 2.0 + movement.length(),
+                    true,
+                    QueryFilter::default()
+                        .exclude_collider(entity)
+                        .exclude_sensors()
+                        .predicate(&|hit| {
+                            player.is_none_or(|(player_entity, _)| hit != player_entity)
+                        }),
+                )
+                .is_some()
+        {
+            seeker.velocity = Vec2::ZERO;
+        } else {
+            transform.translation += movement.extend(0.0);
+        }
+
+        sprite.color = if behaviour.current == SeekerState::Stunned {
+            SEEKER_STUNNED_COLOR
+        } else {
+            SEEKER_COLOR
+        };
+    }
+}
+
+// 冲刺撞到追踪者使其眩晕，否则角色死亡
+pub fn seeker_contact(
+    rapier_context: Single<&RapierContext>,
+    mut q_player: Query<(Entity, &Transform, &mut Velocity), With<Player>>,
+    mut q_seeker: Query<(
+        Entity,
+        &mut Seeker,
+        &mut BehaviourState<SeekerState>,
+        &GlobalTransform,
+    )>,
+    mut player_bounce: PlayerBounce,
+    mut kill_player_ew: EventWriter<KillPlayerEvent>,
+) {
+    let Ok((player_entity, player_transform, mut velocity)) = q_player.get_single_mut() else {
+        return;
+    };
+    for (entity, mut seeker, mut behaviour, transform) in &mut q_seeker {
+        if behaviour.current == SeekerState::Stunned
+            || rapier_context.intersection_pair(player_entity, entity) != Some(true)
+        {
+            continue;
+        }
+        if player_bounce.dashing() {
+            info!("Seeker stunned");
+            let direction = (transform.translation().truncate()
+                - player_transform.translation.truncate())
+            .normalize_or_zero();
+            seeker.velocity = direction * SEEKER_KNOCKBACK_SPEED;
+            behaviour.transition(SeekerState::Stunned);
+            // 角色反弹并恢复冲刺
+            player_bounce.bounce(
+                &mut velocity,
+                Vec2::new(
+                    -direction.x.signum() * PLAYER_SEEKER_BOUNCE_SPEED.x,
+                    PLAYER_SEEKER_BOUNCE_SPEED.y,
+                ),
+            );
+            player_bounce.lock_move(PLAYER_SEEKER_CANNOT_MOVE_TIME);
+        } else {
+            kill_player_ew.send_default();
+        }
+    }
+}
+
+// 玩家死亡后追踪者回到初始位置
+pub fn seeker_reset(
+    mut room_reset_er: EventReader<RoomResetEvent>,
+    mut q_seeker: Query<(
+        &mut Seeker,
+        &mut BehaviourState<SeekerState>,
+        &mut Transform,
+    )>,
+) {
+    if room_reset_er.read().next().is_none() {
+        return;
+    }
+    for (mut seeker, mut behaviour, mut transform) in &mut q_seeker {
+        if let Some(origin) = seeker.origin {
+            transform.translation = origin;
+        }
+        seeker.velocity = Vec2::ZERO;
+        seeker.current_waypoint = 0;
+        seeker.lost_sight = 0.0;
+        *behaviour = BehaviourState::new(SeekerState::Patrol);
+    }
+}
@@ -1,7 +1,9 @@
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
 use crate::{
+    dream_block::{DreamDashOverEvent, DreamDashStartEvent},
     level::Player,
     player::{DashOverEvent, DashStartEvent, NextToSomething, PlayerGrounded, PlayerNextTo},
 };
@@ -15,6 +17,17 @@ pub enum PlayerState {
     Dashing,
     Jumping,
     Climbing,
+    // 穿越梦境方块
+    DreamDashing,
+}
+
+// 冲刺和穿越梦境方块的开始/结束事件
+#[derive(SystemParam)]
+pub struct DashEvents<'w, 's> {
+    dash_start_er: EventReader<'w, 's, DashStartEvent>,
+    dash_over_er: EventReader<'w, 's, DashOverEvent>,
+    dream_dash_start_er: EventReader<'w, 's, DreamDashStartEvent>,
+    dream_dash_over_er: EventReader<'w, 's, DreamDashOverEvent>,
 }
 
 pub fn player_state_machine(
@@ -23,17 +36,28 @@ pub fn player_state_machine(
     mut player_state: ResMut<PlayerState>,
     player_grounded: Res<PlayerGrounded>,
     player_next_to: Res<PlayerNextTo>,
-    mut dash_start_er: EventReader<DashStartEvent>,
-    mut dash_over_er: EventReader<DashOverEvent>,
+    mut dash_events: DashEvents,
 ) {
+    if *player_state == PlayerState::DreamDashing {
+        if dash_events.dream_dash_over_er.read().next().is_none() {
+            // 持续保持dream dashing状态直至接收到DreamDashOverEvent
+            return;
+        }
+        // 离开梦境方块后重新判断状态
+        *player_state = PlayerState::Jumping;
+    }
     if q_player.is_empty() {
         return;
     }
-    if dash_start_er.read().next().is_some() {
+    if dash_events.dream_dash_start_er.read().next().is_some() {
+        *player_state = PlayerState::DreamDashing;
+        return;
+    }
+    if dash_events.dash_start_er.read().next().is_some() {
         *player_state = PlayerState::Dashing;
         return;
     }
-    if *player_state == PlayerState::Dashing && dash_over_er.read().next().is_none() {
+    if *player_state == PlayerState::Dashing && dash_events.dash_over_er.read().next().is_none() {
         // 持续保持dashing状态直至接收到DashOverEvent
         return;
     }
@@ -65,3 +89,37 @@ pub fn player_state_machine(
         return;
     }
 }
+
+// 通用的行为状态机，用于敌人等实体的AI
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct BehaviourState<S> {
+    pub current: S,
+    // 进入当前状态后经过的时间
+    pub elapsed: f32,
+}
+
+impl<S: Copy + PartialEq> BehaviourState<S> {
+    pub fn new(state: S) -> Self {
+        BehaviourState {
+            current: state,
+            elapsed: 0.0,
+        }
+    }
+
+    // 切换状态，状态不变时不重置计时
+    pub fn transition(&mut self, next: S) {
+        if next != self.current {
+            self.current = next;
+            self.elapsed = 0.0;
+        }
+    }
+}
+
+pub fn behaviour_state_tick<S: Send + Sync + 'static>(
+    mut q_behaviour: Query<&mut BehaviourState<S>>,
+    time: Res<Time>,
+) {
+    for mut behaviour in &mut q_behaviour {
+        behaviour.elapsed += time.delta_secs();
+    }
+}
@@ -0,0 +1,199 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    camera::CameraShakeEvent,
+    common::TILE_SIZE,
+    fields::optional_field,
+    level::{Player, RoomResetEvent},
+    platform::{PlatformPath, PlatformVelocity},
+    state_machine::PlayerState,
+};
+
+// 冲刺时触发开关的距离
+const TOUCH_SWITCH_DASH_RADIUS: f32 = TILE_SIZE * 1.5;
+const TOUCH_SWITCH_IDLE_COLOR: Color = Color::srgb(0.4, 0.4, 0.5);
+const TOUCH_SWITCH_ACTIVE_COLOR: Color = Color::srgb(1.0, 0.9, 0.4);
+const SWITCH_GATE_COLOR: Color = Color::srgb(0.6, 0.6, 0.8);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TouchSwitchState {
+    #[default]
+    Idle,
+    // 已触发，玩家死亡后恢复
+    Active,
+    // 所有开关都已触发，不再恢复
+    Locked,
+}
+
+// 触摸开关
+#[derive(Debug, Component, Clone, Default)]
+pub struct TouchSwitch {
+    // 同组的开关控制同组的门
+    pub group: String,
+    pub state: TouchSwitchState,
+}
+
+impl From<&EntityInstance> for TouchSwitch {
+    fn from(entity_instance: &EntityInstance) -> TouchSwitch {
+        TouchSwitch {
+            group: optional_field(entity_instance, entity_instance.get_string_field("Group"))
+                .cloned()
+                .unwrap_or_default(),
+            state: TouchSwitchState::Idle,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SwitchGateState {
+    #[default]
+    Closed,
+    Opening,
+    Open,
+}
+
+// 开关门，所有关联的开关触发后移动到打开位置
+#[derive(Debug, Component, Clone, Default)]
+pub struct SwitchGate {
+    // LDtk中引用的开关，为空时使用同一房间内同组的开关
+    pub switches: Vec<Entity>,
+    pub group: String,
+    pub path: PlatformPath,
+    pub state: SwitchGateState,
+}
+
+impl SwitchGate {
+    pub fn new(entity_instance: &EntityInstance, switches: Vec<Entity>) -> SwitchGate {
+        let mut path = PlatformPath::from(entity_instance);
+        // 只取第一个路径点作为打开位置
+        path.points.truncate(2);
+        SwitchGate {
+            switches,
+            group: optional_field(entity_instance, entity_instance.get_string_field("Group"))
+                .cloned()
+                .unwrap_or_default(),
+            path,
+            state: SwitchGateState::Closed,
+        }
+    }
+}
+
+pub fn setup_touch_switch(mut q_switch: Query<&mut Sprite, Added<TouchSwitch>>) {
+    for mut sprite in &mut q_switch {
+        sprite.color = TOUCH_SWITCH_IDLE_COLOR;
+    }
+}
+
+pub fn setup_switch_gate(mut q_gate: Query<&mut Sprite, Added<SwitchGate>>) {
+    for mut sprite in &mut q_gate {
+        sprite.color = SWITCH_GATE_COLOR;
+    }
+}
+
+// 玩家触碰或冲刺经过开关
+pub fn touch_switch_activate(
+    rapier_context: Single<&RapierContext>,
+    q_player: Query<(Entity, &Transform), With<Player>>,
+    mut q_switch: Query<(Entity, &mut TouchSwitch, &GlobalTransform, &mut Sprite)>,
+    player_state: Res<PlayerState>,
+) {
+    let Ok((player_entity, player_transform)) = q_player.get_single() else {
+        return;
+    };
+    let player_pos = player_transform.translation.truncate();
+    for (entity, mut touch_switch, transform, mut sprite) in &mut q_switch {
+        if touch_switch.state != TouchSwitchState::Idle {
+            continue;
+        }
+        let touched = rapier_context.intersection_pair(player_entity, entity) == Some(true)
+            || (*player_state == PlayerState::Dashing
+                && player_pos.distance(transform.translation().truncate())
+                    < TOUCH_SWITCH_DASH_RADIUS);
+        if touched {
+            info!("Touch switch activated");
+            touch_switch.state = TouchSwitchState::Active;
+            sprite.color = TOUCH_SWITCH_ACTIVE_COLOR;
+        }
+    }
+}
+
+// 玩家死亡后未全部触发的开关恢复
+pub fn touch_switch_reset(
+    mut room_reset_er: EventReader<RoomResetEvent>,
+    mut q_switch: Query<(&mut TouchSwitch, &mut Sprite)>,
+) {
+    if room_reset_er.read().next().is_none() {
+        return;
+    }
+    for (mut touch_switch, mut sprite) in &mut q_switch {
+        if touch_switch.state == TouchSwitchState::Active {
+            touch_switch.state = TouchSwitchState::Idle;
+            sprite.color = TOUCH_SWITCH_IDLE_COLOR;
+        }
+    }
+}
+
+// 开关门
+pub fn switch_gate_update(
+    mut q_gate: Query<(
+        &mut SwitchGate,
+        &mut PlatformVelocity,
+        &mut Transform,
+        &Parent,
+    )>,
+    mut q_switch: Query<(Entity, &mut TouchSwitch, &Parent)>,
+    mut camera_shake_ew: EventWriter<CameraShakeEvent>,
+    time: Res<Time>,
+) {
+    if time.delta_secs() <= 0.0 {
+        return;
+    }
+    for (mut gate, mut platform_velocity, mut transform, gate_parent) in &mut q_gate {
+        let mut movement = Vec2::ZERO;
+        match gate.state {
+            SwitchGateState::Closed => {
+                // 同一图层即同一房间
+                let linked: Vec<Entity> = q_switch
+                    .iter()
+                    .filter(|(entity, touch_switch, parent)| {
+                        if gate.switches.is_empty() {
+                            parent.get() == gate_parent.get() && touch_switch.group == gate.group
+                        } else {
+                            gate.switches.contains(entity)
+                        }
+                    })
+                    .map(|(entity, _, _)| entity)
+                    .collect();
+                let all_active = !linked.is_empty()
+                    && linked.iter().all(|entity| {
+                        q_switch.get(*entity).is_ok_and(|(_, touch_switch, _)| {
+                            touch_switch.state != TouchSwitchState::Idle
+                        })
+                    });
+                if all_active {
+                    info!("Switch gate opening");
+                    for entity in linked {
+                        if let Ok((_, mut touch_switch, _)) = q_switch.get_mut(entity) {
+                            touch_switch.state = TouchSwitchState::Locked;
+                        }
+                    }
+                    gate.state = SwitchGateState::Opening;
+                }
+            }
+            SwitchGateState::Opening => {
+                let path = &mut gate.path;
+                let (speed, easing) = (path.speed, path.easing);
+                movement = path.advance(speed, easing, time.delta_secs());
+                if path.segment == 1 || path.points.len() < 2 {
+                    camera_shake_ew.send_default();
+                    gate.state = SwitchGateState::Open;
+                }
+            }
+            SwitchGateState::Open => {}
+        }
+        transform.translation += movement.extend(0.0);
+        platform_velocity.0 = movement / time.delta_secs();
+    }
+}
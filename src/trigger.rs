@@ -0,0 +1,323 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+use std::collections::HashSet;
+
+use crate::{
+    camera::CameraLock,
+    fields::{optional_field, LdtkEnum},
+    level::{Player, RoomResetEvent},
+    music::MusicChangeEvent,
+    ui::DialogueEvent,
+    weather::WeatherPreset,
+};
+
+// 触发器动作
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriggerAction {
+    // 相机锁定在触发器中心
+    LockCamera,
+    UnlockCamera,
+    PlayMusic(String),
+    StopMusic,
+    Dialogue(String),
+    SetFlag(String),
+    ClearFlag(String),
+    // 显示/隐藏Targets引用的实体
+    Spawn,
+    Despawn,
+    Weather(WeatherPreset),
+}
+
+impl TriggerAction {
+    // 解析"动作"或"动作:参数"格式的字符串
+    pub fn parse(value: &str) -> Option<TriggerAction> {
+        let (name, argument) = match value.split_once(':') {
+            Some((name, argument)) => (name.trim(), argument.trim()),
+            None => (value.trim(), ""),
+        };
+        let action = match name {
+            "camera_lock" => TriggerAction::LockCamera,
+            "camera_unlock" => TriggerAction::UnlockCamera,
+            "music" => TriggerAction::PlayMusic(argument.to_string()),
+            "music_stop" => TriggerAction::StopMusic,
+            "dialogue" => TriggerAction::Dialogue(argument.to_string()),
+            "flag" => TriggerAction::SetFlag(argument.to_string()),
+            "unflag" => TriggerAction::ClearFlag(argument.to_string()),
+            "spawn" => TriggerAction::Spawn,
+            "despawn" => TriggerAction::Despawn,
+            "weather" => TriggerAction::Weather(WeatherPreset::from_variant(argument)?),
+            _ => return None,
+        };
+        Some(action)
+    }
+}
+
+// 触发器区域，角色进入/离开时执行LDtk中配置的动作
+#[derive(Debug, Component, Clone, Default)]
+pub struct Trigger {
+    pub half_size: Vec2,
+    pub on_enter: Vec<TriggerAction>,
+    pub on_exit: Vec<TriggerAction>,
+    // spawn/despawn动作作用的实体
+    pub targets: Vec<Entity>,
+    // 只触发一次
+    pub once: bool,
+    pub fired: bool,
+    pub inside: bool,
+}
+
+impl Trigger {
+    pub fn new(entity_instance: &EntityInstance, targets: Vec<Entity>) -> Trigger {
+        let parse_actions = |identifier: &str| -> Vec<TriggerAction> {
+            optional_field(
+                entity_instance,
+                entity_instance.get_maybe_strings_field(identifier),
+            )
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|value| {
+                let action = TriggerAction::parse(value);
+                if action.is_none() {
+                    error!(
+                        "{}({}): unknown trigger action {:?}",
+                        entity_instance.identifier, entity_instance.iid, value
+                    );
+                }
+                action
+            })
+            .collect()
+        };
+        Trigger {
+            half_size: Vec2::new(
+                entity_instance.width as f32 / 2.0,
+                entity_instance.height as f32 / 2.0,
+            ),
+            on_enter: parse_actions("OnEnter"),
+            on_exit: parse_actions("OnExit"),
+            targets,
+            once: optional_field(entity_instance, entity_instance.get_bool_field("Once"))
+                .is_some_and(|once| *once),
+            fired: false,
+            inside: false,
+        }
+    }
+
+    // 有spawn动作时Targets初始隐藏，由触发器显示
+    pub fn targets_initially_hidden(&self) -> bool {
+        self.on_enter
+            .iter()
+            .chain(self.on_exit.iter())
+            .any(|action| *action == TriggerAction::Spawn)
+    }
+
+    fn acts_on_targets(&self) -> bool {
+        self.on_enter
+            .iter()
+            .chain(self.on_exit.iter())
+            .any(|action| matches!(action, TriggerAction::Spawn | TriggerAction::Despawn))
+    }
+}
+
+// 显示/隐藏触发器引用的实体，隐藏时同时禁用碰撞体
+pub fn set_targets_visible(commands: &mut Commands, targets: &[Entity], visible: bool) {
+    for target in targets {
+        let Some(mut entity_commands) = commands.get_entity(*target) else {
+            continue;
+        };
+        if visible {
+            entity_commands
+                .insert(Visibility::Inherited)
+                .remove::<ColliderDisabled>();
+        } else {
+            entity_commands.insert((Visibility::Hidden, ColliderDisabled));
+        }
+    }
+}
+
+// 触发器执行动作事件
+#[derive(Debug, Clone, PartialEq, Event)]
+pub struct TriggerActionEvent {
+    pub trigger: Entity,
+    pub action: TriggerAction,
+}
+
+// 触发器设置的标记，供谜题等逻辑查询
+#[derive(Debug, Default, Resource)]
+pub struct GameFlags(pub HashSet<String>);
+
+// 玩家死亡后触发器引用的实体恢复初始状态，触发器可以再次触发
+pub fn trigger_reset(
+    mut commands: Commands,
+    mut room_reset_er: EventReader<RoomResetEvent>,
+    mut q_trigger: Query<&mut Trigger>,
+) {
+    if room_reset_er.read().next().is_none() {
+        return;
+    }
+    for mut trigger in &mut q_trigger {
+        if !trigger.acts_on_targets() {
+            continue;
+        }
+        let visible = !trigger.targets_initially_hidden();
+        set_targets_visible(&mut commands, &trigger.targets, visible);
+        trigger.fired = false;
+    }
+}
+
+// 检测角色进入/离开触发器
+pub fn trigger_detect(
+    mut q_trigger: Query<(Entity, &mut Trigger, &GlobalTransform)>,
+    q_player: Query<&Transform, With<Player>>,
+    mut trigger_action_ew: EventWriter<TriggerActionEvent>,
+) {
+    let player_pos = q_player
+        .get_single()
+        .ok()
+        .map(|transform| transform.translation.truncate());
+    for (entity, mut trigger, transform) in &mut q_trigger {
+        let inside = player_pos.is_some_and(|pos| {
+            let offset = (pos - transform.translation().truncate()).abs();
+            offset.x < trigger.half_size.x && offset.y < trigger.half_size.y
+        });
+        if inside == trigger.inside {
+            continue;
+        }
+        trigger.inside = inside;
+        if trigger.once && trigger.fired {
+            continue;
+        }
+        let actions = if inside {
+            info!("Trigger entered");
+            // 没有离开动作时，进入即算触发完成
+            trigger.fired = trigger.on_exit.is_empty();
+            trigger.on_enter.clone()
+        } else {
+            info!("Trigger exited");
+            trigger.fired = true;
+            trigger.on_exit.clone()
+        };
+        trigger_action_ew.send_batch(actions.into_iter().map(|action| TriggerActionEvent {
+            trigger: entity,
+            action,
+        }));
+    }
+}
+
+// 触发器动作修改的全局状态
+#[derive(SystemParam)]
+pub struct TriggerGlobals<'w> {
+    camera_lock: ResMut<'w, CameraLock>,
+    game_flags: ResMut<'w, GameFlags>,
+    weather_preset: ResMut<'w, WeatherPreset>,
+}
+
+// 执行触发器动作
+pub fn trigger_run_actions(
+    mut commands: Commands,
+    mut trigger_action_er: EventReader<TriggerActionEvent>,
+    q_trigger: Query<(&Trigger, &GlobalTransform)>,
+    mut globals: TriggerGlobals,
+    mut music_change_ew: EventWriter<MusicChangeEvent>,
+    mut dialogue_ew: EventWriter<DialogueEvent>,
+) {
+    for event in trigger_action_er.read() {
+        let Ok((trigger, transform)) = q_trigger.get(event.trigger) else {
+            continue;
+        };
+        match &event.action {
+            TriggerAction::LockCamera => {
+                globals.camera_lock.0 = Some(transform.translation().truncate());
+            }
+            TriggerAction::UnlockCamera => globals.camera_lock.0 = None,
+            TriggerAction::PlayMusic(track) => {
+                music_change_ew.send(MusicChangeEvent {
+                    track: Some(track.clone()),
+                });
+            }
+            TriggerAction::StopMusic => {
+                music_change_ew.send(MusicChangeEvent { track: None });
+            }
+            TriggerAction::Dialogue(text) => {
+                dialogue_ew.send(DialogueEvent { text: text.clone() });
+            }
+            TriggerAction::SetFlag(flag) => {
+                globals.game_flags.0.insert(flag.clone());
+            }
+            TriggerAction::ClearFlag(flag) => {
+                globals.game_flags.0.remove(flag);
+            }
+            TriggerAction::Spawn => {
+                set_targets_visible(&mut commands, &trigger.targets, true);
+            }
+            // 只隐藏，玩家死亡后由trigger_reset恢复
+            TriggerAction::Despawn => {
+                set_targets_visible(&mut commands, &trigger.targets, false);
+            }
+            TriggerAction::Weather(preset) => {
+                *globals.weather_preset = *preset;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_actions_without_argument() {
+        assert_eq!(
+            TriggerAction::parse("camera_lock"),
+            Some(TriggerAction::LockCamera)
+        );
+        assert_eq!(
+            TriggerAction::parse("camera_unlock"),
+            Some(TriggerAction::UnlockCamera)
+        );
+        assert_eq!(
+            TriggerAction::parse("music_stop"),
+            Some(TriggerAction::StopMusic)
+        );
+        assert_eq!(TriggerAction::parse("spawn"), Some(TriggerAction::Spawn));
+        assert_eq!(
+            TriggerAction::parse(" despawn "),
+            Some(TriggerAction::Despawn)
+        );
+    }
+
+    #[test]
+    fn parses_actions_with_argument() {
+        assert_eq!(
+            TriggerAction::parse("music: summit"),
+            Some(TriggerAction::PlayMusic("summit".to_string()))
+        );
+        assert_eq!(
+            TriggerAction::parse("dialogue:Hello: Madeline"),
+            Some(TriggerAction::Dialogue("Hello: Madeline".to_string()))
+        );
+        assert_eq!(
+            TriggerAction::parse("flag:door_open"),
+            Some(TriggerAction::SetFlag("door_open".to_string()))
+        );
+        assert_eq!(
+            TriggerAction::parse("unflag:door_open"),
+            Some(TriggerAction::ClearFlag("door_open".to_string()))
+        );
+        assert_eq!(
+            TriggerAction::parse("weather:Blizzard"),
+            Some(TriggerAction::Weather(WeatherPreset::Blizzard))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_actions() {
+        assert_eq!(TriggerAction::parse(""), None);
+        assert_eq!(TriggerAction::parse("jump"), None);
+        assert_eq!(TriggerAction::parse("Camera_Lock"), None);
+        assert_eq!(TriggerAction::parse("weather"), None);
+        assert_eq!(TriggerAction::parse("weather:Rain"), None);
+    }
+}
@@ -1,7 +1,11 @@
 use bevy::color;
 use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
 
-use crate::common::AppState;
+use crate::{
+    collectible::StrawberryRecord, common::AppState, goal::LevelResult, level_info::LevelInfo,
+    level_pack::LevelPack,
+};
 
 #[derive(Component)]
 pub struct OnStartMenuScreen;
@@ -78,3 +82,270 @@ pub fn cleanup_start_menu(
         commands.entity(entity).despawn_recursive();
     }
 }
+
+#[derive(Component)]
+pub struct OnGamingHud;
+
+#[derive(Component)]
+pub struct StrawberryCountText;
+
+#[derive(Component)]
+pub struct LevelNameText;
+
+pub fn setup_game_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            OnGamingHud,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                LevelNameText,
+                Text::new(""),
+                TextColor(color::palettes::basic::WHITE.into()),
+                TextFont {
+                    font: asset_server.load("fonts/ThaleahFat_TTF.ttf"),
+                    font_size: 30.0,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                StrawberryCountText,
+                Text::new("STRAWBERRY 0"),
+                TextColor(color::palettes::basic::RED.into()),
+                TextFont {
+                    font: asset_server.load("fonts/ThaleahFat_TTF.ttf"),
+                    font_size: 30.0,
+                    ..default()
+                },
+            ));
+        });
+}
+
+// 显示当前关卡已收集的草莓数
+pub fn update_strawberry_hud(
+    mut q_text: Query<&mut Text, With<StrawberryCountText>>,
+    q_level: Query<&LevelIid>,
+    strawberry_record: Res<StrawberryRecord>,
+) {
+    if !strawberry_record.is_changed() {
+        return;
+    }
+    let count: usize = q_level
+        .iter()
+        .map(|level_iid| strawberry_record.count(level_iid.get()))
+        .sum();
+    for mut text in &mut q_text {
+        text.0 = format!("STRAWBERRY {}", count);
+    }
+}
+
+// 显示章节和关卡名
+pub fn update_level_name_hud(
+    mut q_text: Query<&mut Text, With<LevelNameText>>,
+    level_info: Res<LevelInfo>,
+) {
+    if !level_info.is_changed() {
+        return;
+    }
+    for mut text in &mut q_text {
+        text.0 = if level_info.chapter.is_empty() {
+            level_info.name.clone()
+        } else {
+            format!("{} - {}", level_info.chapter, level_info.name)
+        };
+    }
+}
+
+pub fn cleanup_game_hud(mut commands: Commands, q_hud: Query<Entity, With<OnGamingHud>>) {
+    for entity in &q_hud {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// 对话显示时长
+const DIALOGUE_TIME: f32 = 3.0;
+
+// 显示一行对话
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub struct DialogueEvent {
+    pub text: String,
+}
+
+#[derive(Component)]
+pub struct DialogueBox(Timer);
+
+pub fn show_dialogue(
+    mut commands: Commands,
+    mut dialogue_er: EventReader<DialogueEvent>,
+    mut q_dialogue: Query<(Entity, &mut DialogueBox)>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+) {
+    if let Some(event) = dialogue_er.read().last() {
+        // 新对话替换旧对话
+        for (entity, _) in &q_dialogue {
+            commands.entity(entity).despawn_recursive();
+        }
+        commands
+            .spawn((
+                OnGamingHud,
+                DialogueBox(Timer::from_seconds(DIALOGUE_TIME, TimerMode::Once)),
+                Node {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(40.0),
+                    width: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    Text::new(event.text.clone()),
+                    TextColor(color::palettes::basic::WHITE.into()),
+                    TextFont {
+                        font: asset_server.load("fonts/ThaleahFat_TTF.ttf"),
+                        font_size: 30.0,
+                        ..default()
+                    },
+                ));
+            });
+        return;
+    }
+    for (entity, mut dialogue_box) in &mut q_dialogue {
+        dialogue_box.0.tick(time.delta());
+        if dialogue_box.0.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct OnResultsScreen;
+
+// 结算界面
+pub fn setup_results_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level_result: Res<LevelResult>,
+    level_info: Res<LevelInfo>,
+) {
+    let font = asset_server.load("fonts/ThaleahFat_TTF.ttf");
+    let format_time = |time: f32| format!("{:02}:{:05.2}", (time / 60.0) as u32, time % 60.0);
+    let mut lines = vec![
+        (
+            level_info.name.to_uppercase(),
+            color::palettes::basic::WHITE,
+            30.0,
+        ),
+        (
+            "LEVEL COMPLETE".to_string(),
+            color::palettes::basic::WHITE,
+            50.0,
+        ),
+        (
+            format!("TIME {}", format_time(level_result.time)),
+            color::palettes::basic::GRAY,
+            30.0,
+        ),
+        (
+            format!("DEATHS {}", level_result.deaths),
+            color::palettes::basic::GRAY,
+            30.0,
+        ),
+        (
+            format!("STRAWBERRY {}", level_result.strawberries),
+            color::palettes::basic::RED,
+            30.0,
+        ),
+    ];
+    // 达到目标时间时高亮
+    if let Some(par_time) = level_info.par_time {
+        lines.push((
+            format!("PAR {}", format_time(par_time)),
+            if level_result.time <= par_time {
+                color::palettes::basic::YELLOW
+            } else {
+                color::palettes::basic::GRAY
+            },
+            30.0,
+        ));
+    }
+    lines.push((
+        "PRESS ENTER KEY".to_string(),
+        color::palettes::basic::GRAY,
+        30.0,
+    ));
+
+    commands
+        .spawn((
+            OnResultsScreen,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            for (text, text_color, font_size) in lines {
+                parent.spawn((
+                    Text::new(text),
+                    TextColor(text_color.into()),
+                    TextFont {
+                        font: font.clone(),
+                        font_size,
+                        ..default()
+                    },
+                ));
+            }
+        });
+}
+
+// 进入下一关，没有下一关则回到开始菜单
+pub fn results_continue(
+    mut app_state: ResMut<NextState<AppState>>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut level_pack: ResMut<LevelPack>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    level_result: Res<LevelResult>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Enter) {
+        return;
+    }
+    match level_result.next_level {
+        Some(index) => {
+            *level_selection = LevelSelection::index(index);
+            app_state.set(AppState::Gaming);
+        }
+        // 本章最后一关，进入下一章
+        None if level_pack.advance_chapter() => {
+            info!("Chapter {}", level_pack.current().name);
+            *level_selection = LevelSelection::index(0);
+            app_state.set(AppState::Gaming);
+        }
+        None => {
+            level_pack.current_chapter = level_pack.start_chapter;
+            *level_selection = level_pack.start_level.clone();
+            app_state.set(AppState::StartMenu);
+        }
+    }
+}
+
+pub fn cleanup_results_screen(
+    mut commands: Commands,
+    q_results: Query<Entity, With<OnResultsScreen>>,
+) {
+    for entity in &q_results {
+        commands.entity(entity).despawn_recursive();
+    }
+}
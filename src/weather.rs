@@ -1,17 +1,51 @@
 use bevy::render::render_resource::{AsBindGroup, ShaderRef};
-use bevy::sprite::{AlphaMode2d, Material2d};
 use bevy::sprite::Material2dPlugin;
+use bevy::sprite::{AlphaMode2d, Material2d};
 
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
 
+use crate::{fields::LdtkEnum, wind::Wind};
+
+// 风力对雪花飘动速度的影响系数
+const WEATHER_WIND_FACTOR: f32 = 0.02;
+// 暴风雪时额外的风力
+const WEATHER_BLIZZARD_WIND: Vec2 = Vec2::new(-80.0, -20.0);
+
+// 天气预设
+#[derive(Debug, Default, Resource, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherPreset {
+    Clear,
+    #[default]
+    Snow,
+    Blizzard,
+}
+
+impl LdtkEnum for WeatherPreset {
+    fn from_variant(variant: &str) -> Option<Self> {
+        match variant {
+            "Clear" => Some(WeatherPreset::Clear),
+            "Snow" => Some(WeatherPreset::Snow),
+            "Blizzard" => Some(WeatherPreset::Blizzard),
+            _ => None,
+        }
+    }
+}
+
 // Plugin that will insert Weather at Z = -10.0, use the custom 'Star Nest' shader
 pub struct WeatherPlugin;
 impl Plugin for WeatherPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(Material2dPlugin::<WeatherMaterial>::default())
             .add_systems(Startup, (spawn_weather,))
-            .add_systems(Update, (update_weather_time,));
+            .add_systems(
+                Update,
+                (
+                    update_weather_time,
+                    update_weather_wind,
+                    update_weather_preset,
+                ),
+            );
     }
 }
 
@@ -22,20 +56,30 @@ fn spawn_weather(
     mut materials: ResMut<Assets<WeatherMaterial>>,
 ) {
     commands.spawn((
+        Weather,
         Mesh2d(meshes.add(Mesh::from(Rectangle::default().mesh()))),
         Transform {
             translation: Vec3::new(0.0, 0.0, 1.0),
             scale: Vec3::new(400., 265., 1.0), //适配分辨率 0.3倍
             ..Default::default()
         },
-        MeshMaterial2d(materials.add(WeatherMaterial { time: 0.0 })),
+        MeshMaterial2d(materials.add(WeatherMaterial {
+            time: 0.0,
+            wind_offset: Vec2::ZERO,
+        })),
     ));
 }
 
+#[derive(Component)]
+struct Weather;
+
 #[derive(AsBindGroup, Debug, Clone, TypePath, Asset)]
 struct WeatherMaterial {
     #[uniform(0)]
     time: f32,
+    // 风力累计产生的雪花偏移
+    #[uniform(0)]
+    wind_offset: Vec2,
 }
 
 impl Material2d for WeatherMaterial {
@@ -53,3 +97,36 @@ fn update_weather_time(time: Res<Time>, mut weathers: ResMut<Assets<WeatherMater
         weather.time += time.delta_secs();
     }
 }
+
+// 雪花随风飘动
+fn update_weather_wind(
+    time: Res<Time>,
+    wind: Res<Wind>,
+    weather_preset: Res<WeatherPreset>,
+    mut weathers: ResMut<Assets<WeatherMaterial>>,
+) {
+    let mut wind = wind.0;
+    if *weather_preset == WeatherPreset::Blizzard {
+        wind += WEATHER_BLIZZARD_WIND;
+    }
+    for (_, weather) in weathers.iter_mut() {
+        weather.wind_offset += wind * WEATHER_WIND_FACTOR * time.delta_secs();
+    }
+}
+
+// 晴天时隐藏雪花
+fn update_weather_preset(
+    weather_preset: Res<WeatherPreset>,
+    mut q_weather: Query<&mut Visibility, With<Weather>>,
+) {
+    if !weather_preset.is_changed() {
+        return;
+    }
+    for mut visibility in &mut q_weather {
+        *visibility = if *weather_preset == WeatherPreset::Clear {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
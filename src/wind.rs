@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    fields::{optional_enum_field, optional_field, LdtkEnum},
+    level::Player,
+    state_machine::PlayerState,
+};
+
+// 风力默认强度
+const WIND_DEFAULT_STRENGTH: f32 = 40.0;
+// 不同状态下风力对角色的影响系数
+pub const WIND_RUNNING_FACTOR: f32 = 0.5;
+pub const WIND_JUMPING_FACTOR: f32 = 1.0;
+const WIND_CLIMBING_FACTOR: f32 = 0.3;
+// 竖直方向风力加速度与强度之比
+const WIND_VERTICAL_ACCELERATION: f32 = 10.0;
+
+// 风区
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct WindZone {
+    pub wind: Vec2,
+    pub half_size: Vec2,
+    // 开启/关闭时长，off_time为0表示一直开启
+    pub on_time: f32,
+    pub off_time: f32,
+    pub elapsed: f32,
+    pub active: bool,
+}
+
+// LDtk中的风向枚举
+struct WindDirection(Vec2);
+
+impl LdtkEnum for WindDirection {
+    fn from_variant(variant: &str) -> Option<Self> {
+        match variant {
+            "Left" => Some(WindDirection(Vec2::NEG_X)),
+            "Right" => Some(WindDirection(Vec2::X)),
+            "Up" => Some(WindDirection(Vec2::Y)),
+            "Down" => Some(WindDirection(Vec2::NEG_Y)),
+            _ => None,
+        }
+    }
+}
+
+impl From<&EntityInstance> for WindZone {
+    fn from(entity_instance: &EntityInstance) -> WindZone {
+        let float_field = |identifier: &str, default: f32| {
+            optional_field(entity_instance, entity_instance.get_float_field(identifier))
+                .copied()
+                .unwrap_or(default)
+        };
+        let direction = optional_enum_field(entity_instance, "Direction")
+            .unwrap_or(WindDirection(Vec2::X))
+            .0;
+        WindZone {
+            wind: direction * float_field("Strength", WIND_DEFAULT_STRENGTH),
+            half_size: Vec2::new(
+                entity_instance.width as f32 / 2.0,
+                entity_instance.height as f32 / 2.0,
+            ),
+            on_time: float_field("OnTime", 0.0),
+            off_time: float_field("OffTime", 0.0),
+            elapsed: 0.0,
+            active: true,
+        }
+    }
+}
+
+// 当前作用于角色的风力，同时用于天气效果
+#[derive(Debug, Default, Resource)]
+pub struct Wind(pub Vec2);
+
+// 风区开关循环
+pub fn wind_zone_cycle(mut q_wind_zone: Query<&mut WindZone>, time: Res<Time>) {
+    for mut wind_zone in &mut q_wind_zone {
+        if wind_zone.off_time <= 0.0 {
+            continue;
+        }
+        wind_zone.elapsed += time.delta_secs();
+        let period = wind_zone.on_time + wind_zone.off_time;
+        wind_zone.elapsed %= period;
+        wind_zone.active = wind_zone.elapsed < wind_zone.on_time;
+    }
+}
+
+// 检测角色所在风区
+pub fn wind_detect(
+    q_wind_zone: Query<(&WindZone, &GlobalTransform)>,
+    q_player: Query<&Transform, With<Player>>,
+    mut wind: ResMut<Wind>,
+) {
+    let Ok(player_transform) = q_player.get_single() else {
+        wind.0 = Vec2::ZERO;
+        return;
+    };
+    let player_pos = player_transform.translation.truncate();
+    wind.0 = q_wind_zone
+        .iter()
+        .filter(|(wind_zone, transform)| {
+            let offset = (player_pos - transform.translation().truncate()).abs();
+            wind_zone.active && offset.x < wind_zone.half_size.x && offset.y < wind_zone.half_size.y
+        })
+        .map(|(wind_zone, _)| wind_zone.wind)
+        .sum();
+}
+
+// 风力作用于角色
+pub fn player_wind(
+    mut q_player: Query<&mut Velocity, With<Player>>,
+    wind: Res<Wind>,
+    player_state: Res<PlayerState>,
+    time: Res<Time>,
+) {
+    if q_player.is_empty() || wind.0 == Vec2::ZERO {
+        return;
+    }
+    let factor = match *player_state {
+        PlayerState::Standing | PlayerState::Running => WIND_RUNNING_FACTOR,
+        PlayerState::Jumping => WIND_JUMPING_FACTOR,
+        PlayerState::Climbing => WIND_CLIMBING_FACTOR,
+        PlayerState::Dashing | PlayerState::DreamDashing => return,
+    };
+    let mut velocity = q_player.single_mut();
+    // 水平方向由player_run和player_move作为目标速度的偏移处理，竖直方向作为加速度
+    velocity.linvel.y += wind.0.y * factor * WIND_VERTICAL_ACCELERATION * time.delta_secs();
+}